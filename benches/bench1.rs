@@ -1,8 +1,8 @@
 use std::time::Instant;
 
 use compressed_vec::{
-    buffered::{BufCVec, BufferedCVec},
-    CVec,
+    buffered::{BufCVec, BufCVecMulti, BufCVecRef, BufferedCVec},
+    CVec, CVecBuilder,
 };
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
 
@@ -22,6 +22,30 @@ fn push_bench(c: &mut Criterion) {
     });
 }
 
+fn push_10k(c: &mut Criterion) {
+    c.bench_function("cvec push 10k", |b| {
+        b.iter(|| {
+            let mut vec = CVec::new();
+            for i in 0..10_000 {
+                vec.push(black_box(i));
+            }
+            vec
+        })
+    });
+}
+
+fn builder_10k(c: &mut Criterion) {
+    c.bench_function("cvec builder 10k", |b| {
+        b.iter(|| {
+            let mut builder = CVecBuilder::with_capacity(10_000);
+            for i in 0..10_000 {
+                builder.push(black_box(i));
+            }
+            builder.build()
+        })
+    });
+}
+
 fn extend_many(c: &mut Criterion) {
     c.bench_function("cvec extend 10k", |b| {
         b.iter_custom(|iters| {
@@ -56,6 +80,42 @@ fn extend_some(c: &mut Criterion) {
     });
 }
 
+fn extend_from_slice(c: &mut Criterion) {
+    c.bench_function("cvec extend_from_slice 10k", |b| {
+        b.iter_custom(|iters| {
+            let to_add = (0..10000).collect::<Vec<u32>>();
+            let mut vec = CVec::new();
+
+            let start = Instant::now();
+
+            for _ in 0..iters {
+                vec.extend_from_slice(black_box(&to_add));
+            }
+
+            start.elapsed()
+        });
+    });
+}
+
+fn from_vec(c: &mut Criterion) {
+    c.bench_function("cvec from vec 1M", |b| {
+        let vec = (0..1_000_000).collect::<Vec<u32>>();
+        b.iter(|| CVec::from(black_box(vec.clone())));
+    });
+}
+
+fn push_sorted(c: &mut Criterion) {
+    c.bench_function("cvec push sorted 100k", |b| {
+        b.iter(|| {
+            let mut vec = CVec::new_sorted();
+            for i in 0..100_000 {
+                vec.push(black_box(i));
+            }
+            vec
+        })
+    });
+}
+
 fn get_rand(c: &mut Criterion) {
     c.bench_function("cvec get() random", |b| {
         b.iter_custom(|iters| {
@@ -72,6 +132,39 @@ fn get_rand(c: &mut Criterion) {
     });
 }
 
+fn get_unchecked_rand(c: &mut Criterion) {
+    c.bench_function("cvec get_unchecked() random", |b| {
+        b.iter_custom(|iters| {
+            let vec = (0..iters as u32).collect::<CVec>();
+
+            let start = Instant::now();
+
+            for i in 0..iters {
+                black_box(unsafe { vec.get_unchecked(i as usize * 100 % vec.len()) });
+            }
+
+            start.elapsed()
+        });
+    });
+}
+
+fn get_into_rand(c: &mut Criterion) {
+    c.bench_function("cvec get_into() random", |b| {
+        b.iter_custom(|iters| {
+            let vec = (0..iters as u32).collect::<CVec>();
+            let mut scratch = Vec::new();
+
+            let start = Instant::now();
+
+            for i in 0..iters {
+                black_box(vec.get_into(i as usize * 100 % vec.len(), &mut scratch));
+            }
+
+            start.elapsed()
+        });
+    });
+}
+
 fn get_seq(c: &mut Criterion) {
     c.bench_function("cvec get() seq.", |b| {
         b.iter_custom(|iters| {
@@ -105,6 +198,37 @@ fn get_seq_buf(c: &mut Criterion) {
     });
 }
 
+fn get_ping_pong(c: &mut Criterion) {
+    let vec = (0..100_000).collect::<CVec>();
+
+    c.bench_function("cvec get() ping-pong buffered", |b| {
+        let mut buf = BufCVecRef::new(&vec);
+        b.iter(|| {
+            for i in 0..1000 {
+                black_box(buf.get_buffered(i));
+                black_box(buf.get_buffered(99_999 - i));
+            }
+        })
+    });
+
+    c.bench_function("cvec get() ping-pong multi buffered", |b| {
+        let mut buf = BufCVecMulti::new(&vec, 2);
+        b.iter(|| {
+            for i in 0..1000 {
+                black_box(buf.get_buffered(i));
+                black_box(buf.get_buffered(99_999 - i));
+            }
+        })
+    });
+}
+
+fn iter_max(c: &mut Criterion) {
+    c.bench_function("cvec iter max", |b| {
+        let vec = (0..100_000).collect::<CVec>();
+        b.iter(|| black_box(vec.iter().max()));
+    });
+}
+
 fn pop(c: &mut Criterion) {
     c.bench_function("cvec pop", |b| {
         b.iter_custom(|iters| {
@@ -124,12 +248,21 @@ fn pop(c: &mut Criterion) {
 criterion_group!(
     benches,
     push_bench,
+    push_10k,
+    builder_10k,
     extend_some,
     extend_many,
+    extend_from_slice,
+    from_vec,
+    push_sorted,
     pop,
     get_seq,
     get_rand,
-    get_seq_buf
+    get_unchecked_rand,
+    get_into_rand,
+    get_seq_buf,
+    get_ping_pong,
+    iter_max
 );
 
 criterion_main!(benches);
@@ -0,0 +1,28 @@
+//! Compares the compressed size of a vector in the different encodings. Unlike the other benches
+//! this doesn't measure time, run it with `cargo bench --bench size`.
+
+use compressed_vec::{CVec, Encoding};
+
+fn main() {
+    let values = (0..100_000).collect::<Vec<u32>>();
+
+    let plain = CVec::from(values.as_slice());
+    let mut sorted = CVec::new_sorted();
+    sorted.extend_from_slice(&values);
+    let mut frame_of_reference = CVec::with_encoding(Encoding::FrameOfReference);
+    frame_of_reference.extend_from_slice(&values);
+
+    println!("(0..100_000) as Vec<u32>: {} bytes", values.len() * 4);
+    for (name, vec) in [
+        ("plain", &plain),
+        ("sorted", &sorted),
+        ("frame of reference", &frame_of_reference),
+    ] {
+        println!(
+            "(0..100_000) {}: {} bytes, ratio {:.1}",
+            name,
+            vec.byte_len(),
+            vec.compression_ratio()
+        );
+    }
+}
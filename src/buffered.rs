@@ -1,6 +1,6 @@
-use crate::CVec;
+use crate::{iter::BufCVecIter, CVec};
 use bitpacking::{BitPacker, BitPacker8x};
-use std::mem;
+use std::collections::VecDeque;
 
 /// A trait defining functionality for buffered reading of a collection. This reduces en/decode
 /// operations on a CVec value
@@ -32,25 +32,184 @@ impl Buffer {
         }
     }
 
+    /// Create a new buffer with the data for a whole block preallocated. This avoids resizing the
+    /// buffer on the first read.
+    #[inline]
+    pub fn with_capacity() -> Self {
+        Self {
+            data: vec![0u32; BitPacker8x::BLOCK_LEN],
+            buf_block: None,
+        }
+    }
+
+    /// Returns the amount of values the buffer can hold without reallocating
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.data.capacity()
+    }
+
+    /// Drops the cached block, so the next read decompresses it again. Has to be called if the
+    /// vector the buffer reads from got modified.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.buf_block = None;
+    }
+
+    /// Writes `val` into the cached block if it holds position `index`
+    #[inline]
+    fn update(&mut self, index: usize, val: u32) {
+        if self.buf_block == Some(CVec::pos_block(index)) {
+            self.data[CVec::pos_in_block(index)] = val;
+        }
+    }
+
+    /// Drops the cached block if it is the block `block_nr`
+    #[inline]
+    pub fn invalidate_block(&mut self, block_nr: usize) {
+        if self.buf_block == Some(block_nr) {
+            self.clear();
+        }
+    }
+
     pub fn read_buffered(&mut self, vec: &CVec, index: usize) -> Option<&u32> {
         if index >= vec.len() {
             return None;
         }
 
-        let block_index = CVec::pos_block(index);
+        self.read_block(vec, CVec::pos_block(index))
+            .get(CVec::pos_in_block(index))
+    }
 
+    /// Returns all values of the block at `block_index` and caches it if it isn't already.
+    pub(crate) fn read_block(&mut self, vec: &CVec, block_index: usize) -> &[u32] {
         if self.buf_block.is_none() || *self.buf_block.as_ref().unwrap() != block_index {
             // Set cache
-            let mut buff = mem::take(&mut self.data);
             if self.data.len() < BitPacker8x::BLOCK_LEN {
                 self.data.resize(BitPacker8x::BLOCK_LEN, 0);
             }
-            vec.decompress_block(block_index, &mut buff);
-            self.data = buff;
+            vec.decompress_block(block_index, &mut self.data);
             self.buf_block = Some(block_index);
         }
 
-        self.data.get(CVec::pos_in_block(index))
+        &self.data
+    }
+
+    /// Reduces the values within `start..end` of `vec` to a single value by applying `f` on whole
+    /// blocks at once. Returns `None` if the range is empty.
+    pub(crate) fn reduce_range<F>(
+        &mut self,
+        vec: &CVec,
+        start: usize,
+        end: usize,
+        f: F,
+    ) -> Option<u32>
+    where
+        F: Fn(u32, u32) -> u32,
+    {
+        let mut res: Option<u32> = None;
+        let mut pos = start;
+
+        while pos < end {
+            let from = CVec::pos_in_block(pos);
+            let to = (from + end - pos).min(BitPacker8x::BLOCK_LEN);
+            let block = self.read_block(vec, CVec::pos_block(pos));
+
+            let reduced = block[from..to].iter().copied().reduce(&f);
+            res = match (res, reduced) {
+                (Some(a), Some(b)) => Some(f(a, b)),
+                (a, b) => a.or(b),
+            };
+
+            pos += to - from;
+        }
+
+        res
+    }
+}
+
+/// A buffer caching multiple decompressed blocks of a [`CVec`]. If all blocks are in use, the least
+/// recently used one gets evicted. Useful if reads alternate between a few distant positions.
+#[derive(Debug, Clone)]
+pub struct MultiBuffer {
+    /// Cached blocks and their indices, the most recently used first
+    blocks: VecDeque<(usize, Vec<u32>)>,
+    max_blocks: usize,
+}
+
+impl MultiBuffer {
+    /// Create a new buffer which caches up to `n` blocks
+    ///
+    /// # Panics
+    /// Panics if `n` is 0
+    #[inline]
+    pub fn with_blocks(n: usize) -> Self {
+        assert!(n > 0, "a MultiBuffer needs to hold at least one block");
+
+        Self {
+            blocks: VecDeque::with_capacity(n),
+            max_blocks: n,
+        }
+    }
+
+    /// Returns the maximum amount of cached blocks
+    #[inline]
+    pub fn max_blocks(&self) -> usize {
+        self.max_blocks
+    }
+
+    pub fn read_buffered(&mut self, vec: &CVec, index: usize) -> Option<&u32> {
+        if index >= vec.len() {
+            return None;
+        }
+
+        self.read_block(vec, CVec::pos_block(index))
+            .get(CVec::pos_in_block(index))
+    }
+
+    /// Returns all values of the block at `block_index` and caches it if it isn't already.
+    fn read_block(&mut self, vec: &CVec, block_index: usize) -> &[u32] {
+        match self.blocks.iter().position(|(i, _)| *i == block_index) {
+            Some(pos) => {
+                let block = self.blocks.remove(pos).unwrap();
+                self.blocks.push_front(block);
+            }
+            None => {
+                // Reuse the buffer of the least recently used block
+                let mut data = if self.blocks.len() >= self.max_blocks {
+                    self.blocks.pop_back().unwrap().1
+                } else {
+                    vec![0u32; BitPacker8x::BLOCK_LEN]
+                };
+                vec.decompress_block(block_index, &mut data);
+                self.blocks.push_front((block_index, data));
+            }
+        }
+
+        &self.blocks[0].1
+    }
+}
+
+/// A wrapper around a borrowed [`CVec`], which caches multiple blocks using a [`MultiBuffer`]
+#[derive(Debug, Clone)]
+pub struct BufCVecMulti<'a> {
+    vec: &'a CVec,
+    buf: MultiBuffer,
+}
+
+impl<'a> BufCVecMulti<'a> {
+    /// Create a new BufCVecMulti from a CVec reference which caches up to `n` blocks
+    #[inline]
+    pub fn new(vec: &'a CVec, n: usize) -> Self {
+        Self {
+            vec,
+            buf: MultiBuffer::with_blocks(n),
+        }
+    }
+
+    /// Like CVec::get() but returns a reference to the u32 and uses the cached blocks
+    #[inline]
+    pub fn get_buffered(&mut self, index: usize) -> Option<&u32> {
+        self.buf.read_buffered(self.vec, index)
     }
 }
 
@@ -76,6 +235,52 @@ impl BufCVec {
     pub fn get_buffered(&mut self, index: usize) -> Option<&u32> {
         self.buf.read_buffered(&self.vec, index)
     }
+
+    /// Returns an iterator over the values which reads through the buffer without consuming the
+    /// `BufCVec`. Values get yielded by value since they're only borrowed from the buffer.
+    #[inline]
+    pub fn iter(&mut self) -> BufCVecIter<'_> {
+        BufCVecIter::new(self)
+    }
+
+    /// Pushes a value onto the wrapped vector. If the last block is cached, the value gets written
+    /// into the cache as well instead of dropping it.
+    #[inline]
+    pub fn push(&mut self, val: u32) {
+        let pos = self.vec.len();
+        self.vec.push(val);
+        self.buf.update(pos, val);
+    }
+
+    /// Sets the value at `index` like `CVec::set`. If the block of `index` is cached, the value
+    /// gets written into the cache as well instead of dropping it.
+    #[inline]
+    pub fn set(&mut self, index: usize, val: u32) -> Option<()> {
+        self.vec.set(index, val)?;
+        self.buf.update(index, val);
+        Some(())
+    }
+
+    /// Pops the last value of the wrapped vector. The cache stays valid since only values which
+    /// are still in the vector can be read from it.
+    #[inline]
+    pub fn pop(&mut self) -> Option<u32> {
+        self.vec.pop()
+    }
+
+    /// Returns a mutable reference to the wrapped vector. Since the vector can be modified
+    /// arbitrarily through it, the cached block gets dropped.
+    #[inline]
+    pub fn get_vec_mut(&mut self) -> &mut CVec {
+        self.buf.clear();
+        &mut self.vec
+    }
+
+    /// Returns the wrapped vector and its buffer
+    #[inline]
+    pub(crate) fn parts(&mut self) -> (&CVec, &mut Buffer) {
+        (&self.vec, &mut self.buf)
+    }
 }
 
 impl From<CVec> for BufCVec {
@@ -113,6 +318,170 @@ impl<'a> BufCVecRef<'a> {
     pub fn get_buffered(&mut self, index: usize) -> Option<&u32> {
         self.buf.read_buffered(&self.vec, index)
     }
+
+    /// Returns the wrapped vector and its buffer
+    #[inline]
+    pub(crate) fn parts(&mut self) -> (&CVec, &mut Buffer) {
+        (self.vec, &mut self.buf)
+    }
+}
+
+/// A wrapper around a mutably borrowed [`CVec`], which buffers writes to the same block. Values
+/// set with `set_buffered` are only written into the cached block, which gets compressed once
+/// when another block is accessed, on `flush` or when the wrapper gets dropped.
+#[derive(Debug)]
+pub struct BufCVecMut<'a> {
+    vec: &'a mut CVec,
+    buf: Buffer,
+    dirty: bool,
+}
+
+impl<'a> From<&'a mut CVec> for BufCVecMut<'a> {
+    #[inline]
+    fn from(cvec: &'a mut CVec) -> Self {
+        BufCVecMut::new(cvec)
+    }
+}
+
+impl<'a> BufCVecMut<'a> {
+    /// Create a new BufCVecMut from a mutable CVec reference
+    #[inline]
+    pub fn new(vec: &'a mut CVec) -> Self {
+        Self {
+            vec,
+            buf: Buffer::new(),
+            dirty: false,
+        }
+    }
+
+    /// Like `CVec::get` but returns a reference to the u32 and uses the cached block. Returns
+    /// values which weren't flushed yet.
+    #[inline]
+    pub fn get_buffered(&mut self, index: usize) -> Option<&u32> {
+        if index >= self.vec.len() {
+            return None;
+        }
+
+        self.load_block(CVec::pos_block(index));
+        self.buf.data.get(CVec::pos_in_block(index))
+    }
+
+    /// Sets the value at `index` to `val` within the cached block. Returns `None` if `index` is
+    /// out of bounds.
+    pub fn set_buffered(&mut self, index: usize, val: u32) -> Option<()> {
+        if index >= self.vec.len() {
+            return None;
+        }
+
+        self.load_block(CVec::pos_block(index));
+        self.buf.data[CVec::pos_in_block(index)] = val;
+        self.dirty = true;
+        Some(())
+    }
+
+    /// Writes the cached block back into the vector if it was modified
+    pub fn flush(&mut self) {
+        if !self.dirty {
+            return;
+        }
+
+        let block_nr = self.buf.buf_block.unwrap();
+        let len = self.vec.block_len(block_nr);
+        self.vec.replace_block(block_nr, &self.buf.data[..len]);
+        self.dirty = false;
+    }
+
+    /// Caches the block `block_nr`, flushing the previously cached one if necessary
+    #[inline]
+    fn load_block(&mut self, block_nr: usize) {
+        if self.buf.buf_block != Some(block_nr) {
+            self.flush();
+            self.buf.read_block(self.vec, block_nr);
+        }
+    }
+}
+
+impl<'a> Drop for BufCVecMut<'a> {
+    #[inline]
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
+
+/// A streaming writer appending values to a mutably borrowed [`CVec`] in batches of arbitrary
+/// size. Values get collected until they complete a block, so only whole blocks get compressed and
+/// appended to the vector. The remaining values get appended on `flush`, `finish` or when the
+/// writer gets dropped.
+#[derive(Debug)]
+pub struct CVecWriter<'a> {
+    vec: &'a mut CVec,
+    buf: Vec<u32>,
+}
+
+impl<'a> From<&'a mut CVec> for CVecWriter<'a> {
+    #[inline]
+    fn from(cvec: &'a mut CVec) -> Self {
+        CVecWriter::new(cvec)
+    }
+}
+
+impl<'a> CVecWriter<'a> {
+    /// Create a new CVecWriter appending to `vec`
+    #[inline]
+    pub fn new(vec: &'a mut CVec) -> Self {
+        Self {
+            vec,
+            buf: Vec::with_capacity(BitPacker8x::BLOCK_LEN),
+        }
+    }
+
+    /// Appends `values` to the vector. All blocks completed by `values` get appended directly,
+    /// the rest gets buffered until more values are written.
+    pub fn write(&mut self, values: &[u32]) {
+        // The last block of the vector might be partially filled already
+        let offset = CVec::pos_in_block(self.vec.len());
+        let total = offset + self.buf.len() + values.len();
+        if total < BitPacker8x::BLOCK_LEN {
+            self.buf.extend_from_slice(values);
+            return;
+        }
+
+        // Amount of values of `values` which fill up blocks completely
+        let aligned = total / BitPacker8x::BLOCK_LEN * BitPacker8x::BLOCK_LEN;
+        let split = aligned - offset - self.buf.len();
+
+        if self.buf.is_empty() {
+            self.vec.extend_from_slice(&values[..split]);
+        } else {
+            self.buf.extend_from_slice(&values[..split]);
+            self.vec.extend_from_slice(&self.buf);
+            self.buf.clear();
+        }
+
+        self.buf.extend_from_slice(&values[split..]);
+    }
+
+    /// Appends all buffered values to the vector, even if they don't fill a block
+    #[inline]
+    pub fn flush(&mut self) {
+        if !self.buf.is_empty() {
+            self.vec.extend_from_slice(&self.buf);
+            self.buf.clear();
+        }
+    }
+
+    /// Appends all buffered values and releases the vector
+    #[inline]
+    pub fn finish(mut self) {
+        self.flush();
+    }
+}
+
+impl<'a> Drop for CVecWriter<'a> {
+    #[inline]
+    fn drop(&mut self) {
+        self.flush();
+    }
 }
 
 impl BufferedCVec for BufCVec {
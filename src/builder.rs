@@ -0,0 +1,73 @@
+use crate::{CVec, Encoding};
+
+/// Collects plain values and compresses them all at once when the vector gets built. Unlike
+/// pushing onto a `CVec`, no block gets compressed before all values are known, which makes this
+/// the fastest way to construct a vector value by value.
+#[derive(Debug, Clone, Default)]
+pub struct CVecBuilder {
+    values: Vec<u32>,
+    encoding: Encoding,
+}
+
+impl CVecBuilder {
+    /// Create a new, empty builder
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a new builder with space for `capacity` values preallocated
+    #[inline]
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            values: Vec::with_capacity(capacity),
+            ..Self::default()
+        }
+    }
+
+    /// Sets the encoding the built vector compresses its blocks with
+    #[inline]
+    pub fn encoding(mut self, encoding: Encoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
+    /// Adds a value to the end of the vector
+    #[inline]
+    pub fn push(&mut self, val: u32) {
+        self.values.push(val);
+    }
+
+    /// Adds all values of `slice` to the end of the vector
+    #[inline]
+    pub fn extend_from_slice(&mut self, slice: &[u32]) {
+        self.values.extend_from_slice(slice);
+    }
+
+    /// Returns the amount of collected values
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Returns true if no values were collected yet
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Compresses all collected values into a new vector
+    #[inline]
+    pub fn build(self) -> CVec {
+        let mut vec = CVec::with_encoding(self.encoding);
+        vec.extend_from_slice(&self.values);
+        vec
+    }
+}
+
+impl Extend<u32> for CVecBuilder {
+    #[inline]
+    fn extend<T: IntoIterator<Item = u32>>(&mut self, iter: T) {
+        self.values.extend(iter);
+    }
+}
@@ -0,0 +1,237 @@
+use std::{convert::TryFrom, iter::FromIterator};
+
+use serde::{de::Error as _, Deserialize, Deserializer, Serialize};
+
+use crate::{error::LaneLenMismatchError, iter::CVecIterRef, CVec};
+
+/// A compressed `Vec<u64>`. Each value gets split into its low and high 32 bits which are stored
+/// in two separate `CVec` lanes. Both lanes get bitpacked independently, so values below
+/// `u32::MAX` compress as well as in a `CVec` since their high lane blocks only contain 0s and
+/// get stored with a bitsize of 0. Values exceeding 32 bits only cost as many extra bits as
+/// their high parts need.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash, Serialize)]
+pub struct CVec64 {
+    /// The lower 32 bits of each value
+    low: CVec,
+
+    /// The upper 32 bits of each value
+    high: CVec,
+}
+
+impl CVec64 {
+    /// Constructs a new, empty `CVec64`
+    #[inline]
+    pub fn new() -> Self {
+        Self {
+            low: CVec::new(),
+            high: CVec::new(),
+        }
+    }
+
+    /// Allocate a new compressed vector which can store `capacity` numbers without reallocating
+    #[inline]
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            low: CVec::with_capacity(capacity),
+            high: CVec::with_capacity(capacity),
+        }
+    }
+
+    /// Returns the number of elements in the vector
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.low.len()
+    }
+
+    /// Returns true if the vector is empty
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.low.is_empty()
+    }
+
+    /// Returns the amount of allocated bytes by the vector
+    #[inline]
+    pub fn byte_len(&self) -> usize {
+        self.low.byte_len().saturating_add(self.high.byte_len())
+    }
+
+    /// Pushes a new value on top of the vector
+    #[inline]
+    pub fn push(&mut self, val: u64) {
+        let (low, high) = Self::split(val);
+        self.low.push(low);
+        self.high.push(high);
+    }
+
+    /// Pops the last element from the vector. Returns `None` if vector is empty or Some(val)
+    /// with the popped value.
+    #[inline]
+    pub fn pop(&mut self) -> Option<u64> {
+        // Check both lanes first so a missing value in one of them doesn't leave them uneven
+        let val = Self::join(self.low.last()?, self.high.last()?);
+        self.low.pop();
+        self.high.pop();
+        Some(val)
+    }
+
+    /// Returns the u64 at `pos`
+    #[inline]
+    pub fn get(&self, pos: usize) -> Option<u64> {
+        Some(Self::join(self.low.get(pos)?, self.high.get(pos)?))
+    }
+
+    /// Replaces the value at `pos` with `new`. Returns `None` if `pos` is out of bounds.
+    #[inline]
+    pub fn set(&mut self, pos: usize, new: u64) -> Option<()> {
+        let (low, high) = Self::split(new);
+        self.low.set(pos, low)?;
+        self.high.set(pos, high)
+    }
+
+    /// Returns the last value of the vector
+    #[inline]
+    pub fn last(&self) -> Option<u64> {
+        self.get(self.len().checked_sub(1)?)
+    }
+
+    /// Removes all values from the vector
+    #[inline]
+    pub fn clear(&mut self) {
+        self.low.clear();
+        self.high.clear();
+    }
+
+    /// Returns an referenced iterator over the vector's elements
+    #[inline]
+    pub fn iter(&self) -> CVec64Iter<'_> {
+        CVec64Iter {
+            low: self.low.iter(),
+            high: self.high.iter(),
+        }
+    }
+
+    /// Returns the data hold by CVec64 decompressed as `Vec::<u64>`
+    #[inline]
+    pub fn as_vec(&self) -> Vec<u64> {
+        self.iter().collect()
+    }
+
+    #[inline]
+    fn split(val: u64) -> (u32, u32) {
+        (val as u32, (val >> 32) as u32)
+    }
+
+    #[inline]
+    fn join(low: u32, high: u32) -> u64 {
+        (high as u64) << 32 | low as u64
+    }
+}
+
+impl<'de> Deserialize<'de> for CVec64 {
+    /// Deserializes both lanes and checks that they hold the same amount of values
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        CVec64::try_from(RawCVec64::deserialize(deserializer)?).map_err(D::Error::custom)
+    }
+}
+
+/// The serialized fields of a `CVec64`
+#[derive(Deserialize)]
+struct RawCVec64 {
+    low: CVec,
+    high: CVec,
+}
+
+impl TryFrom<RawCVec64> for CVec64 {
+    type Error = LaneLenMismatchError;
+
+    fn try_from(raw: RawCVec64) -> Result<Self, Self::Error> {
+        if raw.low.len() != raw.high.len() {
+            return Err(LaneLenMismatchError {
+                low: raw.low.len(),
+                high: raw.high.len(),
+            });
+        }
+
+        Ok(Self {
+            low: raw.low,
+            high: raw.high,
+        })
+    }
+}
+
+/// `Iterator` implementing type to iterate over a `&CVec64`
+pub struct CVec64Iter<'a> {
+    low: CVecIterRef<'a>,
+    high: CVecIterRef<'a>,
+}
+
+impl<'a> Iterator for CVec64Iter<'a> {
+    type Item = u64;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        Some(CVec64::join(self.low.next()?, self.high.next()?))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.low.size_hint()
+    }
+}
+
+impl<'a> DoubleEndedIterator for CVec64Iter<'a> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        Some(CVec64::join(self.low.next_back()?, self.high.next_back()?))
+    }
+}
+
+impl<'a> ExactSizeIterator for CVec64Iter<'a> {
+    #[inline]
+    fn len(&self) -> usize {
+        self.low.len()
+    }
+}
+
+impl<'a> IntoIterator for &'a CVec64 {
+    type Item = u64;
+
+    type IntoIter = CVec64Iter<'a>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl Extend<u64> for CVec64 {
+    #[inline]
+    fn extend<T: IntoIterator<Item = u64>>(&mut self, iter: T) {
+        let (low, high): (Vec<u32>, Vec<u32>) = iter.into_iter().map(Self::split).unzip();
+        self.low.extend_from_slice(&low);
+        self.high.extend_from_slice(&high);
+    }
+}
+
+impl FromIterator<u64> for CVec64 {
+    #[inline]
+    fn from_iter<T: IntoIterator<Item = u64>>(iter: T) -> Self {
+        let mut new = CVec64::new();
+        new.extend(iter);
+        new
+    }
+}
+
+impl From<&[u64]> for CVec64 {
+    #[inline]
+    fn from(slice: &[u64]) -> Self {
+        slice.iter().copied().collect()
+    }
+}
+
+impl From<&CVec64> for Vec<u64> {
+    #[inline]
+    fn from(vec: &CVec64) -> Self {
+        vec.as_vec()
+    }
+}
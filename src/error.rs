@@ -0,0 +1,124 @@
+use std::{error::Error, fmt::Display};
+
+/// Error returned when compressing a block of values fails
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompressError {
+    /// More values than fit into a single block were passed. Contains the amount of values.
+    TooManyValues(usize),
+}
+
+impl Display for CompressError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CompressError::TooManyValues(len) => {
+                write!(f, "can't compress {} values into a block of 256", len)
+            }
+        }
+    }
+}
+
+impl Error for CompressError {}
+
+/// Error returned when parsing the output of `CVec::to_bytes` fails
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FromBytesError {
+    /// The data ended before all blocks were read
+    UnexpectedEnd,
+    /// A block header has an invalid bitsize. Contains the header.
+    InvalidHeader(u8),
+    /// There are bytes left after the last block. Contains the amount of bytes.
+    TrailingBytes(usize),
+}
+
+impl Display for FromBytesError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FromBytesError::UnexpectedEnd => write!(f, "unexpected end of data"),
+            FromBytesError::InvalidHeader(header) => {
+                write!(f, "invalid block header {:#010b}", header)
+            }
+            FromBytesError::TrailingBytes(len) => {
+                write!(f, "{} bytes left after the last block", len)
+            }
+        }
+    }
+}
+
+impl Error for FromBytesError {}
+
+/// Error returned by `CVec::from_blocks` if the passed blocks are invalid
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FromBlocksError {
+    /// A block header has an invalid bitsize. Contains the header.
+    InvalidHeader(u8),
+    /// The data of a block doesn't have the length its header requires. Contains the block number.
+    InvalidBlockLen(usize),
+    /// The amount of blocks doesn't match the amount of values. Contains the amount of blocks.
+    BlockCountMismatch(usize),
+    /// The values of a vector in the sorted mode aren't monotonically increasing. Contains the
+    /// number of the first block breaking the order.
+    Unsorted(usize),
+}
+
+impl Display for FromBlocksError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FromBlocksError::InvalidHeader(header) => {
+                write!(f, "invalid block header {:#010b}", header)
+            }
+            FromBlocksError::InvalidBlockLen(block_nr) => {
+                write!(f, "data of block {} doesn't match its header", block_nr)
+            }
+            FromBlocksError::BlockCountMismatch(blocks) => {
+                write!(f, "{} blocks don't match the amount of values", blocks)
+            }
+            FromBlocksError::Unsorted(block_nr) => {
+                write!(f, "block {} of a sorted vector isn't sorted", block_nr)
+            }
+        }
+    }
+}
+
+impl Error for FromBlocksError {}
+
+/// Error returned when converting a `CVec` into a vector of a smaller integer type fails
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ValueOverflowError {
+    /// Position of the first value which doesn't fit into the target type
+    pub pos: usize,
+    /// The value which doesn't fit
+    pub value: u32,
+}
+
+impl Display for ValueOverflowError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "value {} at position {} doesn't fit into the target type",
+            self.value, self.pos
+        )
+    }
+}
+
+impl Error for ValueOverflowError {}
+
+/// Error returned when deserializing a `CVec64` whose lanes hold different amounts of values
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LaneLenMismatchError {
+    /// Amount of values in the lane holding the lower 32 bits
+    pub low: usize,
+    /// Amount of values in the lane holding the upper 32 bits
+    pub high: usize,
+}
+
+impl Display for LaneLenMismatchError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "low lane holds {} values but high lane holds {}",
+            self.low, self.high
+        )
+    }
+}
+
+impl Error for LaneLenMismatchError {}
@@ -1,7 +1,7 @@
 use std::iter::FromIterator;
 
 use crate::{
-    buffered::{BufCVec, BufCVecRef},
+    buffered::{BufCVec, BufCVecRef, Buffer, BufferedCVec},
     CVec,
 };
 
@@ -9,16 +9,30 @@ use crate::{
 pub struct CVecIterRef<'a> {
     vec: BufCVecRef<'a>,
     pos: usize,
-    len: usize,
+    end: usize,
 }
 
 impl<'a> CVecIterRef<'a> {
     #[inline]
     pub(crate) fn new(vec: &'a CVec) -> Self {
+        Self::with_start(vec, 0)
+    }
+
+    /// Turns the iterator into one yielding the block number and the position within the block
+    /// along with each value, so consumers can react to crossing block boundaries.
+    #[inline]
+    pub fn enumerate_blocks(self) -> EnumerateBlocks<'a> {
+        EnumerateBlocks { iter: self }
+    }
+
+    /// Creates a new iterator starting at `start`. Values before `start` never get decompressed.
+    #[inline]
+    pub(crate) fn with_start(vec: &'a CVec, start: usize) -> Self {
+        let end = vec.len();
         Self {
             vec: BufCVecRef::new(vec),
-            pos: 0,
-            len: vec.len(),
+            pos: start.min(end),
+            end,
         }
     }
 }
@@ -28,17 +42,229 @@ impl<'a> Iterator for CVecIterRef<'a> {
 
     #[inline]
     fn next(&mut self) -> Option<Self::Item> {
+        if self.pos >= self.end {
+            return None;
+        }
+
+        let val = self.vec.get_buffered(self.pos)?;
+        self.pos += 1;
+        Some(*val)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.end - self.pos;
+        (remaining, Some(remaining))
+    }
+
+    #[inline]
+    fn max(mut self) -> Option<Self::Item> {
+        let (vec, buf) = self.vec.parts();
+        buf.reduce_range(vec, self.pos, self.end, u32::max)
+    }
+
+    #[inline]
+    fn min(mut self) -> Option<Self::Item> {
+        let (vec, buf) = self.vec.parts();
+        buf.reduce_range(vec, self.pos, self.end, u32::min)
+    }
+}
+
+impl<'a> DoubleEndedIterator for CVecIterRef<'a> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.end <= self.pos {
+            return None;
+        }
+
+        self.end -= 1;
+        self.vec.get_buffered(self.end).copied()
+    }
+}
+
+/// Iterator yielding `(block_nr, pos_in_block, value)` for each value of a `&CVec`. Created by
+/// `CVecIterRef::enumerate_blocks`.
+pub struct EnumerateBlocks<'a> {
+    iter: CVecIterRef<'a>,
+}
+
+impl<'a> Iterator for EnumerateBlocks<'a> {
+    type Item = (usize, usize, u32);
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let pos = self.iter.pos;
+        let val = self.iter.next()?;
+        Some((CVec::pos_block(pos), CVec::pos_in_block(pos), val))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.iter.size_hint()
+    }
+}
+
+impl<'a> ExactSizeIterator for EnumerateBlocks<'a> {
+    #[inline]
+    fn len(&self) -> usize {
+        self.iter.len()
+    }
+}
+
+/// Lending iterator over a `&CVec` which yields references into its buffered block. Since the
+/// references are only valid until the next call, this can't implement `Iterator`.
+pub struct CVecLendingIter<'a> {
+    vec: BufCVecRef<'a>,
+    pos: usize,
+}
+
+impl<'a> CVecLendingIter<'a> {
+    #[inline]
+    pub(crate) fn new(vec: &'a CVec) -> Self {
+        Self {
+            vec: BufCVecRef::new(vec),
+            pos: 0,
+        }
+    }
+
+    /// Returns a reference to the next value. The reference is valid until the next call of
+    /// `next_ref`.
+    #[inline]
+    pub fn next_ref(&mut self) -> Option<&u32> {
+        let val = self.vec.get_buffered(self.pos)?;
+        self.pos += 1;
+        Some(val)
+    }
+}
+
+/// `Iterator` implementing type to iterate over a `&mut BufCVec` without consuming it. Reads go
+/// through the buffer of the `BufCVec`, so its cached block stays warm across multiple scans.
+pub struct BufCVecIter<'a> {
+    vec: &'a mut BufCVec,
+    pos: usize,
+    end: usize,
+}
+
+impl<'a> BufCVecIter<'a> {
+    #[inline]
+    pub(crate) fn new(vec: &'a mut BufCVec) -> Self {
+        let end = vec.get_vec().len();
+        Self { vec, pos: 0, end }
+    }
+}
+
+impl<'a> Iterator for BufCVecIter<'a> {
+    type Item = u32;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.pos >= self.end {
+            return None;
+        }
+
         let val = self.vec.get_buffered(self.pos)?;
         self.pos += 1;
         Some(*val)
     }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.end - self.pos;
+        (remaining, Some(remaining))
+    }
+}
+
+impl<'a> ExactSizeIterator for BufCVecIter<'a> {
+    #[inline]
+    fn len(&self) -> usize {
+        self.end - self.pos
+    }
+}
+
+/// Iterator over the values removed by `CVec::drain`. The values get removed from the vector when
+/// the iterator gets dropped, even if it wasn't fully consumed.
+pub struct Drain<'a> {
+    vec: &'a mut CVec,
+    buf: Buffer,
+    /// Range of values to remove
+    start: usize,
+    end: usize,
+    /// Range of values which weren't yielded yet
+    pos: usize,
+    back: usize,
+}
+
+impl<'a> Drain<'a> {
+    #[inline]
+    pub(crate) fn new(vec: &'a mut CVec, start: usize, end: usize) -> Self {
+        Self {
+            vec,
+            buf: Buffer::new(),
+            start,
+            end,
+            pos: start,
+            back: end,
+        }
+    }
+}
+
+impl<'a> Iterator for Drain<'a> {
+    type Item = u32;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.pos >= self.back {
+            return None;
+        }
+
+        let val = *self.buf.read_buffered(self.vec, self.pos)?;
+        self.pos += 1;
+        Some(val)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.back - self.pos;
+        (remaining, Some(remaining))
+    }
+}
+
+impl<'a> DoubleEndedIterator for Drain<'a> {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.back <= self.pos {
+            return None;
+        }
+
+        self.back -= 1;
+        self.buf.read_buffered(self.vec, self.back).copied()
+    }
+}
+
+impl<'a> ExactSizeIterator for Drain<'a> {
+    #[inline]
+    fn len(&self) -> usize {
+        self.back - self.pos
+    }
+}
+
+impl<'a> Drop for Drain<'a> {
+    fn drop(&mut self) {
+        if self.start == self.end {
+            return;
+        }
+
+        let mut rest = self.vec.split_off(self.end);
+        self.vec.truncate(self.start);
+        self.vec.append(&mut rest);
+    }
 }
 
 /// `Iterator` implementing type to iterate over a `CVec`
 pub struct CVecIter {
     vec: BufCVec,
     pos: usize,
-    len: usize,
+    end: usize,
 }
 
 impl Iterator for CVecIter {
@@ -46,10 +272,44 @@ impl Iterator for CVecIter {
 
     #[inline]
     fn next(&mut self) -> Option<Self::Item> {
+        if self.pos >= self.end {
+            return None;
+        }
+
         let val = self.vec.get_buffered(self.pos)?;
         self.pos += 1;
         Some(*val)
     }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.end - self.pos;
+        (remaining, Some(remaining))
+    }
+
+    #[inline]
+    fn max(mut self) -> Option<Self::Item> {
+        let (vec, buf) = self.vec.parts();
+        buf.reduce_range(vec, self.pos, self.end, u32::max)
+    }
+
+    #[inline]
+    fn min(mut self) -> Option<Self::Item> {
+        let (vec, buf) = self.vec.parts();
+        buf.reduce_range(vec, self.pos, self.end, u32::min)
+    }
+}
+
+impl DoubleEndedIterator for CVecIter {
+    #[inline]
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.end <= self.pos {
+            return None;
+        }
+
+        self.end -= 1;
+        self.vec.get_buffered(self.end).copied()
+    }
 }
 
 impl IntoIterator for CVec {
@@ -60,13 +320,35 @@ impl IntoIterator for CVec {
     #[inline]
     fn into_iter(self) -> Self::IntoIter {
         CVecIter {
-            len: self.len(),
+            end: self.len(),
             vec: BufCVec::new(self),
             pos: 0,
         }
     }
 }
 
+impl<'a> IntoIterator for &'a mut BufCVec {
+    type Item = u32;
+
+    type IntoIter = BufCVecIter<'a>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        BufCVecIter::new(self)
+    }
+}
+
+impl<'a> IntoIterator for &'a CVec {
+    type Item = u32;
+
+    type IntoIter = CVecIterRef<'a>;
+
+    #[inline]
+    fn into_iter(self) -> Self::IntoIter {
+        CVecIterRef::new(self)
+    }
+}
+
 impl FromIterator<u32> for CVec {
     #[inline]
     fn from_iter<T: IntoIterator<Item = u32>>(iter: T) -> Self {
@@ -76,16 +358,23 @@ impl FromIterator<u32> for CVec {
     }
 }
 
+impl<'a> FromIterator<&'a u32> for CVec {
+    #[inline]
+    fn from_iter<T: IntoIterator<Item = &'a u32>>(iter: T) -> Self {
+        iter.into_iter().copied().collect()
+    }
+}
+
 impl ExactSizeIterator for CVecIter {
     #[inline]
     fn len(&self) -> usize {
-        self.len
+        self.end - self.pos
     }
 }
 
 impl<'a> ExactSizeIterator for CVecIterRef<'a> {
     #[inline]
     fn len(&self) -> usize {
-        self.len
+        self.end - self.pos
     }
 }
@@ -5,198 +5,2086 @@
 /// Contains a ro-wrapper around `CVec` that caches read blocks for faster sequencial (or nearby)
 /// access to the `CVec` values.
 pub mod buffered;
+/// Contains `CVecBuilder`, which collects plain values and compresses them at once
+pub mod builder;
+/// Contains `CVec64`, a compressed vector of u64 values
+pub mod cvec64;
+/// Contains the error types of this crate
+pub mod error;
 /// Contains iterator implementations for `CVec`
 pub mod iter;
+/// Contains parallel construction and iteration of `CVec`s using rayon
+#[cfg(feature = "rayon")]
+pub mod par;
+/// Contains (de)serialization of a `CVec` as a flat sequence of values instead of its compressed
+/// blocks. Use it with `#[serde(with = "compressed_vec::serde_values")]` or the `Values` wrapper.
+pub mod serde_values;
 pub mod traits;
 
 pub use buffered::Buffer;
+pub use builder::CVecBuilder;
+pub use cvec64::CVec64;
 
 use bitpacking::{BitPacker, BitPacker8x};
-use iter::CVecIterRef;
+use buffered::BufCVecRef;
+use error::{CompressError, FromBlocksError, FromBytesError, ValueOverflowError};
+use iter::{CVecIterRef, CVecLendingIter, Drain};
 use serde::{Deserialize, Serialize};
-use std::mem::size_of;
+use std::{
+    borrow::Cow,
+    cell::RefCell,
+    collections::VecDeque,
+    convert::TryFrom,
+    mem::{self, size_of},
+    ops::{Bound, Range, RangeBounds},
+    sync::OnceLock,
+};
 use utilsrs::itertools::IterExt;
 
+/// Mask for the bitsize within the header byte of a block
+const BITS_MASK: u8 = 0b0011_1111;
+
+/// Mask for the encoding of a block within its header byte
+const ENCODING_MASK: u8 = 0b1100_0000;
+
+/// Header flag of blocks which are stored as deltas to their first value. The first value is
+/// stored in front of the packed data.
+const DELTA_BLOCK: u8 = 1 << 6;
+
+/// Header flag of zigzag encoded blocks. Blocks holding values which are small if interpreted as
+/// `i32` (eg. `-1i32 as u32`) get zigzag encoded before being packed.
+const ZIGZAG_BLOCK: u8 = 2 << 6;
+
+/// Header flag of blocks which are stored relative to their minimum (frame of reference). The
+/// minimum is stored in front of the packed data.
+const FOR_BLOCK: u8 = 3 << 6;
+
+/// Length of the reference value stored in front of delta and frame of reference blocks
+const REFERENCE_LEN: usize = size_of::<u32>();
+
+/// Length of the header in the binary format of `to_bytes`
+const HEADER_LEN: usize = size_of::<u64>();
+
+thread_local! {
+    /// Buffer single value reads like `get` decompress their block into, so they don't have to
+    /// allocate a new one on every call
+    static SCRATCH: RefCell<Vec<u32>> = RefCell::new(vec![0u32; BitPacker8x::BLOCK_LEN]);
+}
+
+/// The encoding new blocks of a `CVec` get compressed with. Each block is only stored in the
+/// preferred encoding if that's smaller than storing its values as they are.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Encoding {
+    /// Values get packed as they are
+    #[default]
+    Plain,
+
+    /// Sorted blocks get stored as deltas between consecutive values. Works best for sorted data.
+    Delta,
+
+    /// Values get stored relative to the minimum of their block. Works best for data where all
+    /// values of a block are close to each other.
+    FrameOfReference,
+}
+
 /// A compressed `Vec<u32>` which can be compress up to 32 times in size. The level of compression
 /// depends on the bitsize of the biggest value within a 256block.
-#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[derive(Clone, Debug, Default)]
 pub struct CVec {
     /// The compressed Data
     data: Vec<(u8, Vec<u8>)>,
 
     /// Count of items in the vector
     items: usize,
+
+    /// Encoding used for compressing blocks
+    encoding: Encoding,
+
+    /// Whether appended values are required to keep the vector monotonically increasing. Gets
+    /// cleared by methods which overwrite or reorder values in place.
+    sorted: bool,
+
+    /// Minimum and maximum value of each block in `data`, used for skipping blocks in range
+    /// queries. Bounds of blocks which got values removed may be wider than necessary. Never
+    /// gets serialized but rebuilt when deserializing.
+    bounds: Vec<(u32, u32)>,
+
+    /// Decompressed values of the last, partially filled block. `push` and `pop` only modify this
+    /// buffer, which gets compressed into `data` once the block is full or before other methods
+    /// modify the vector. If it isn't empty, the compressed last block in `data` is outdated.
+    /// Never gets serialized, the staged block gets serialized compressed instead.
+    tail: Vec<u32>,
+
+    /// All values decompressed by `as_slice_cached`. Gets dropped by every method modifying the
+    /// vector.
+    cache: Option<Vec<u32>>,
 }
 
-impl CVec {
-    /// Constructs a new, empty `CVec`
+impl CVec {
+    /// Constructs a new, empty `CVec`
+    #[inline]
+    pub fn new() -> Self {
+        Self {
+            data: Vec::new(),
+            bounds: Vec::new(),
+            items: 0,
+            encoding: Encoding::Plain,
+            sorted: false,
+            tail: Vec::new(),
+            cache: None,
+        }
+    }
+
+    /// Constructs a new, empty `CVec` which compresses its blocks using `encoding`
+    #[inline]
+    pub fn with_encoding(encoding: Encoding) -> Self {
+        Self {
+            encoding,
+            ..Self::new()
+        }
+    }
+
+    /// Constructs a new, empty `CVec` for monotonically increasing values like sorted IDs. Blocks
+    /// get stored as deltas to their first value, so only the bits of the differences between
+    /// consecutive values are stored.
+    ///
+    /// # Panics
+    /// `push` panics if the pushed value is smaller than the last value of the vector. Methods
+    /// appending values like `extend`, `extend_from_slice`, `extend_repeat`, `append` or `concat`
+    /// panic the same way. Methods overwriting or reordering values in place like `set`, `insert`,
+    /// `swap` or `reverse` switch the vector out of the sorted mode, so values pushed afterwards
+    /// don't have to be sorted anymore.
+    #[inline]
+    pub fn new_sorted() -> Self {
+        Self {
+            sorted: true,
+            ..Self::with_encoding(Encoding::Delta)
+        }
+    }
+
+    /// Builds a new vector from `values` using the encoding which compresses the data best. The
+    /// encodings are compared on a sample of the blocks.
+    pub fn auto(values: &[u32]) -> Self {
+        let mut new = Self::with_encoding(Self::pick_encoding(values));
+        new.extend_from_slice(values);
+        new
+    }
+
+    /// Returns the encoding which compresses a sample of up to 8 blocks of `values` best.
+    fn pick_encoding(values: &[u32]) -> Encoding {
+        const SAMPLE_BLOCKS: usize = 8;
+
+        let blocks = Self::req_block_count(values.len());
+        let step = (blocks / SAMPLE_BLOCKS).max(1);
+        let mut out = Vec::new();
+
+        // `min_by_key` returns the first minimum, so `Plain` wins ties
+        [Encoding::Plain, Encoding::Delta, Encoding::FrameOfReference]
+            .iter()
+            .copied()
+            .min_by_key(|&encoding| {
+                values
+                    .chunks(BitPacker8x::BLOCK_LEN)
+                    .step_by(step)
+                    .map(|chunk| {
+                        Self::compress(chunk, &mut out, encoding);
+                        out.len()
+                    })
+                    .sum::<usize>()
+            })
+            .unwrap()
+    }
+
+    /// Returns the encoding new blocks get compressed with
+    #[inline]
+    pub fn encoding(&self) -> Encoding {
+        self.encoding
+    }
+
+    /// Allocate a new compressed vector which can store `capacity` numbers without reallocating
+    pub fn with_capacity(capacity: usize) -> Self {
+        let req_blocks = Self::req_block_count(capacity);
+
+        let data = (0..req_blocks)
+            .map(|_| (0, Vec::with_capacity(256)))
+            .collect();
+
+        Self {
+            data,
+            bounds: vec![(0, 0); req_blocks],
+            items: 0,
+            encoding: Encoding::Plain,
+            sorted: false,
+            tail: Vec::new(),
+            cache: None,
+        }
+    }
+
+    /// Collects at most `max` values from `iter` into a new vector. This can be used to bound
+    /// iterators which might be infinite.
+    #[inline]
+    pub fn from_iter_capped<I: IntoIterator<Item = u32>>(iter: I, max: usize) -> Self {
+        iter.into_iter().take(max).collect()
+    }
+
+    /// Builds a new vector from a slice by compressing it block-wise without iterating over
+    /// single elements.
+    pub(crate) fn from_slice(slice: &[u32]) -> Self {
+        let mut new = Self {
+            data: Vec::with_capacity(Self::req_block_count(slice.len())),
+            ..Self::new()
+        };
+        new.extend_from_slice(slice);
+        new
+    }
+
+    /// Compresses a single block of 256 values using `num_bits` bits per value and returns it in
+    /// the same format `CVec` stores its blocks in. Useful for testing the codec without a `CVec`.
+    ///
+    /// # Panics
+    /// Panics if `num_bits > 32` or if a value doesn't fit into `num_bits` bits
+    pub fn from_raw_block(num_bits: u8, values: &[u32; 256]) -> (u8, Vec<u8>) {
+        assert!(num_bits <= 32, "num_bits must be <= 32");
+
+        let bitpacker = Self::bitpacker();
+        assert!(
+            bitpacker.num_bits(values) <= num_bits,
+            "values don't fit into {} bits",
+            num_bits
+        );
+
+        let mut out = vec![0u8; 32 * num_bits as usize];
+        bitpacker.compress(values, &mut out, num_bits);
+        (num_bits, out)
+    }
+
+    /// Serializes the vector into a compact binary format: The amount of values as little endian
+    /// u64 followed by all blocks, each stored as its header byte and its compressed data.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let ranges = self.block_byte_ranges();
+        let mut out = Vec::with_capacity(ranges.last().map_or(HEADER_LEN, |i| i.end));
+
+        out.extend_from_slice(&(self.items as u64).to_le_bytes());
+        for block in self.used_blocks() {
+            let (header, block) = &*block;
+            out.push(*header);
+            out.extend_from_slice(&block[..Self::compressed_len(*header)]);
+        }
+
+        out
+    }
+
+    /// Parses a vector from the output of `to_bytes`. The length of each block is derived from
+    /// its header, so `bytes` has to contain exactly the serialized blocks.
+    pub fn from_bytes(bytes: &[u8]) -> Result<CVec, FromBytesError> {
+        if bytes.len() < HEADER_LEN {
+            return Err(FromBytesError::UnexpectedEnd);
+        }
+
+        let (items, mut rest) = bytes.split_at(HEADER_LEN);
+        let mut items_bytes = [0u8; HEADER_LEN];
+        items_bytes.copy_from_slice(items);
+        let items = u64::from_le_bytes(items_bytes) as usize;
+
+        // Don't trust `items` for preallocating
+        let mut new = CVec::new();
+        let mut values = vec![0u32; BitPacker8x::BLOCK_LEN];
+
+        while new.items < items {
+            let (&header, block) = rest.split_first().ok_or(FromBytesError::UnexpectedEnd)?;
+            if header & BITS_MASK > 32 {
+                return Err(FromBytesError::InvalidHeader(header));
+            }
+
+            let len = Self::compressed_len(header);
+            if block.len() < len {
+                return Err(FromBytesError::UnexpectedEnd);
+            }
+
+            let block_len = (items - new.items).min(BitPacker8x::BLOCK_LEN);
+            Self::decompress(&block[..len], header, &mut values);
+            new.data.push((header, block[..len].to_vec()));
+            new.bounds.push(Self::min_max(&values[..block_len]));
+            new.items += block_len;
+            rest = &block[len..];
+        }
+
+        if !rest.is_empty() {
+            return Err(FromBytesError::TrailingBytes(rest.len()));
+        }
+
+        Ok(new)
+    }
+
+    /// Builds a vector holding `items` values from already compressed blocks as returned by
+    /// `block_at` or `from_raw_block`, without recompressing them. Each block has to hold 256
+    /// values except the last one, and its data has to be exactly as long as its header requires.
+    /// The blocks get decompressed once to compute their bounds.
+    #[inline]
+    pub fn from_blocks(blocks: Vec<(u8, Vec<u8>)>, items: usize) -> Result<CVec, FromBlocksError> {
+        Self::from_blocks_checked(blocks, items, false)
+    }
+
+    /// Like `from_blocks`, but if `sorted` is true the values also have to be monotonically
+    /// increasing and the vector gets created in the sorted mode of `new_sorted`.
+    pub(crate) fn from_blocks_checked(
+        blocks: Vec<(u8, Vec<u8>)>,
+        items: usize,
+        sorted: bool,
+    ) -> Result<CVec, FromBlocksError> {
+        if blocks.len() != Self::req_block_count(items) {
+            return Err(FromBlocksError::BlockCountMismatch(blocks.len()));
+        }
+
+        let mut new = CVec::new();
+        let mut values = vec![0u32; BitPacker8x::BLOCK_LEN];
+        let mut last = 0;
+
+        for (block_nr, (header, data)) in blocks.iter().enumerate() {
+            if header & BITS_MASK > 32 {
+                return Err(FromBlocksError::InvalidHeader(*header));
+            }
+
+            if data.len() != Self::compressed_len(*header) {
+                return Err(FromBlocksError::InvalidBlockLen(block_nr));
+            }
+
+            let block_len = (items - block_nr * BitPacker8x::BLOCK_LEN).min(BitPacker8x::BLOCK_LEN);
+            Self::decompress(data, *header, &mut values);
+            let values = &values[..block_len];
+
+            if sorted {
+                if last > values[0] || values.windows(2).any(|i| i[0] > i[1]) {
+                    return Err(FromBlocksError::Unsorted(block_nr));
+                }
+                last = values[block_len - 1];
+            }
+
+            new.bounds.push(Self::min_max(values));
+        }
+
+        new.data = blocks;
+        new.items = items;
+        new.sorted = sorted;
+        Ok(new)
+    }
+
+    /// Recompresses every block and keeps the result if it's smaller, which shrinks blocks that
+    /// still carry the bitsize of values which got popped or overwritten, or that were built from
+    /// suboptimally packed data. Also releases the unused capacity of the block buffers and
+    /// tightens the cached bounds of each block. Returns the amount of bytes that were saved.
+    pub fn optimize(&mut self) -> usize {
+        self.flush_tail();
+        let before = self.byte_len();
+
+        let mut values = vec![0u32; BitPacker8x::BLOCK_LEN];
+        let mut out = Vec::new();
+
+        for block_nr in 0..self.block_count() {
+            self.decompress_block(block_nr, &mut values).unwrap();
+            let values = &values[..self.block_len(block_nr)];
+            self.bounds[block_nr] = Self::min_max(values);
+
+            let header = Self::compress(values, &mut out, self.encoding);
+            let block = &mut self.data[block_nr];
+            if Self::compressed_len(header) < Self::compressed_len(block.0) {
+                block.0 = header;
+                mem::swap(&mut block.1, &mut out);
+            }
+            block.1.truncate(Self::compressed_len(block.0));
+            block.1.shrink_to_fit();
+        }
+
+        before - self.byte_len()
+    }
+
+    /// Returns the byte range of each block within the output of `to_bytes`. This allows seeking
+    /// to a specific block in serialized data without parsing all previous blocks.
+    pub fn block_byte_ranges(&self) -> Vec<Range<usize>> {
+        let mut pos = HEADER_LEN;
+
+        self.used_blocks()
+            .map(|block| {
+                let start = pos;
+                pos += 1 + Self::compressed_len(block.0);
+                start..pos
+            })
+            .collect()
+    }
+
+    /// Returns the size of the compressed values in bytes, which is roughly the size `to_bytes`
+    /// produces. Unused capacity and bookkeeping like the cached bounds of each block aren't
+    /// counted, use `heap_bytes` for the actual memory usage.
+    #[inline]
+    pub fn byte_len(&self) -> usize {
+        // `items` and initial `data` vec
+        let mut len = size_of::<usize>() * 2;
+
+        // The staged last block counts with its compressed size
+        for block in self.current_blocks() {
+            // u8 size and block size
+            len = len.saturating_add(1).saturating_add(block.1.len());
+        }
+
+        len
+    }
+
+    /// Returns the amount of bytes allocated on the heap by the vector, including unused capacity
+    /// of the block buffers.
+    pub fn capacity_in_bytes(&self) -> usize {
+        let blocks = self.data.capacity() * size_of::<(u8, Vec<u8>)>();
+        let data: usize = self.data.iter().map(|i| i.1.capacity()).sum();
+        blocks + data + self.tail.capacity() * size_of::<u32>()
+    }
+
+    /// Returns all bytes the vector allocated on the heap. Unlike `byte_len` this counts the
+    /// capacity of the outer block vector and of each block buffer, and unlike
+    /// `capacity_in_bytes` also the cached bounds of each block and the values cached by
+    /// `as_slice_cached`. Use it for memory profiling.
+    pub fn heap_bytes(&self) -> usize {
+        let bounds = self.bounds.capacity() * size_of::<(u32, u32)>();
+        let cache = self.cache.as_ref().map_or(0, |i| i.capacity());
+        self.capacity_in_bytes() + bounds + cache * size_of::<u32>()
+    }
+
+    /// Returns the amount of blocks holding values. Preallocated blocks which are still empty
+    /// don't count.
+    #[inline]
+    pub fn block_count(&self) -> usize {
+        Self::req_block_count(self.items)
+    }
+
+    /// Returns the header byte and the compressed data of block `block_nr` in the format
+    /// `from_raw_block` produces, or `None` if the block doesn't hold values. The lower 6 bits of
+    /// the header are the bitsize. The staged last block gets compressed on the fly, so its data
+    /// isn't borrowed.
+    pub fn block_at(&self, block_nr: usize) -> Option<(u8, Cow<'_, [u8]>)> {
+        if block_nr >= self.block_count() {
+            return None;
+        }
+
+        if self.is_staged(block_nr) {
+            let mut out = Vec::new();
+            let header = Self::compress(&self.tail, &mut out, self.encoding);
+            out.truncate(Self::compressed_len(header));
+            return Some((header, Cow::Owned(out)));
+        }
+
+        let (header, data) = &self.data[block_nr];
+        Some((
+            *header,
+            Cow::Borrowed(&data[..Self::compressed_len(*header)]),
+        ))
+    }
+
+    /// Returns for each block holding values whether it is stored zigzag encoded, which is the case
+    /// for blocks holding values that are small when interpreted as `i32`.
+    pub fn block_signed_flags(&self) -> Vec<bool> {
+        self.used_blocks()
+            .map(|block| block.0 & ENCODING_MASK == ZIGZAG_BLOCK)
+            .collect()
+    }
+
+    /// Returns the bitsize each block holding values is packed with. Together with
+    /// `block_signed_flags` this shows which blocks compress badly.
+    pub fn block_bit_sizes(&self) -> Vec<u8> {
+        self.used_blocks()
+            .map(|block| block.0 & BITS_MASK)
+            .collect()
+    }
+
+    /// Returns the ratio between the size of the values stored as `Vec<u32>` and the size of the
+    /// vector as returned by `byte_len`. Values above 1 mean the vector is smaller.
+    #[inline]
+    pub fn compression_ratio(&self) -> f64 {
+        (self.items * size_of::<u32>()) as f64 / self.byte_len() as f64
+    }
+
+    /// Returns the number of elements in the vector
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.items
+    }
+
+    /// Returns true if the vector is empty
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the number of numbers the vector can hold without reallocating
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.data.len() * 256
+    }
+
+    /// Reserves capacity for at least `additional` more values by preallocating empty blocks like
+    /// `with_capacity` does. The length and the values don't change.
+    ///
+    /// # Panics
+    /// Panics if the new capacity overflows `usize`
+    pub fn reserve(&mut self, additional: usize) {
+        let required = self
+            .items
+            .checked_add(additional)
+            .expect("capacity overflow");
+        let req_blocks = Self::req_block_count(required);
+        if req_blocks <= self.data.len() {
+            return;
+        }
+
+        self.data
+            .resize_with(req_blocks, || (0, Vec::with_capacity(256)));
+        self.bounds.resize(req_blocks, (0, 0));
+    }
+
+    /// Drops preallocated blocks which aren't used and shrinks all buffers as much as possible.
+    /// Returns the amount of bytes that were reclaimed.
+    pub fn shrink_to_fit(&mut self) -> usize {
+        let before = self.capacity_in_bytes();
+
+        self.invalidate_cache();
+        self.flush_tail();
+        self.tail.shrink_to_fit();
+        self.data.truncate(Self::req_block_count(self.items));
+        self.bounds.truncate(self.data.len());
+        self.bounds.shrink_to_fit();
+        for block in self.data.iter_mut() {
+            block.1.shrink_to_fit();
+        }
+        self.data.shrink_to_fit();
+
+        // Flushing the staged block allocates its compressed buffer, so the vector might grow
+        before.saturating_sub(self.capacity_in_bytes())
+    }
+
+    /// Pushes a new value on top of the vector
+    ///
+    /// # Panics
+    /// Panics if the vector was created with `new_sorted` and `val` is smaller than the last value.
+    pub fn push(&mut self, val: u32) {
+        self.invalidate_cache();
+        if self.sorted {
+            Self::assert_sorted_after(self.last(), val);
+        }
+
+        if self.tail.is_empty() {
+            self.stage_last_block();
+        }
+
+        self.tail.push(val);
+        self.items += 1;
+
+        let bounds = &mut self.bounds[Self::pos_block(self.items - 1)];
+        *bounds = match self.tail.len() {
+            1 => (val, val),
+            _ => (bounds.0.min(val), bounds.1.max(val)),
+        };
+
+        // Only full blocks get compressed
+        if self.tail.len() == BitPacker8x::BLOCK_LEN {
+            self.flush_tail();
+        }
+    }
+
+    /// Prepares pushing into the last block by decompressing it into `self.tail`, or allocates a
+    /// new block if the last one is full. This happens only once every 256 pushes.
+    #[cold]
+    fn stage_last_block(&mut self) {
+        let in_block = Self::pos_in_block(self.items);
+
+        if in_block != 0 {
+            // The last block was written by another method
+            let mut block = vec![0u32; BitPacker8x::BLOCK_LEN];
+            self.decompress_block(self.last_block(), &mut block)
+                .unwrap();
+            self.tail.extend_from_slice(&block[..in_block]);
+        } else if self.need_new_block() {
+            // Compressed once the block gets flushed
+            self.push_placeholder();
+        }
+    }
+
+    /// Compresses the values staged in `self.tail` into their block. Has to be called by all
+    /// methods which modify blocks directly.
+    fn flush_tail(&mut self) {
+        if self.tail.is_empty() {
+            return;
+        }
+
+        let block_nr = Self::pos_block(self.items - 1);
+        let out_block = &mut self.data[block_nr];
+        out_block.0 = Self::compress(&self.tail, &mut out_block.1, self.encoding);
+        self.bounds[block_nr] = Self::min_max(&self.tail);
+        self.tail.clear();
+    }
+
+    /// Appends `count` copies of `value` to the vector. All full blocks share a single compressed
+    /// block which only gets compressed once, so long runs are cheap to append.
+    pub fn extend_repeat(&mut self, value: u32, count: usize) {
+        self.invalidate_cache();
+        if self.sorted && count > 0 {
+            Self::assert_sorted_after(self.last(), value);
+        }
+        self.flush_tail();
+
+        let run = [value; 256];
+
+        // Fill up the current block
+        let free = (256 - Self::pos_in_block(self.items)) % 256;
+        let head = free.min(count);
+        self.extend_from_slice(&run[..head]);
+        let mut count = count - head;
+
+        if count >= 256 {
+            let mut block = Vec::new();
+            let num_bits = Self::compress(&run, &mut block, self.encoding);
+
+            while count >= 256 {
+                let block_nr = self.last_block();
+                if block_nr >= self.data.len() {
+                    self.push_placeholder();
+                }
+                self.data[block_nr] = (num_bits, block.clone());
+                self.bounds[block_nr] = (value, value);
+
+                self.items += 256;
+                count -= 256;
+            }
+        }
+
+        self.extend_from_slice(&run[..count]);
+    }
+
+    /// Overwrites all values of the vector with `val`. All blocks share a single compressed block
+    /// which only gets compressed once.
+    pub fn fill(&mut self, val: u32) {
+        self.invalidate_cache();
+        // Shorter blocks get padded with their last value, so a full block fits all of them
+        let mut block = Vec::new();
+        let header = Self::compress(&[val; 256], &mut block, self.encoding);
+
+        self.tail.clear();
+        for block_nr in 0..Self::req_block_count(self.items) {
+            self.data[block_nr] = (header, block.clone());
+            self.bounds[block_nr] = (val, val);
+        }
+    }
+
+    /// Resizes the vector to `new_len` like `Vec::resize`. If the vector grows, the new values are
+    /// set to `val`. Otherwise it gets truncated.
+    pub fn resize(&mut self, new_len: usize, val: u32) {
+        self.invalidate_cache();
+        if new_len <= self.items {
+            self.truncate(new_len);
+        } else {
+            self.extend_repeat(val, new_len - self.items);
+        }
+    }
+
+    /// Inserts `val` at position `pos` and shifts all following values to the right.
+    ///
+    /// # Panics
+    /// Panics if `pos > self.len()`
+    pub fn insert(&mut self, pos: usize, val: u32) {
+        self.invalidate_cache();
+        assert!(
+            pos <= self.items,
+            "insertion index (is {}) should be <= len (is {})",
+            pos,
+            self.items
+        );
+        self.sorted = false;
+
+        if pos == self.items {
+            self.push(val);
+            return;
+        }
+
+        self.flush_tail();
+
+        let mut block = vec![0u32; BitPacker8x::BLOCK_LEN];
+        let mut carry = val;
+        let mut start = Self::pos_in_block(pos);
+
+        // Shift all values by one, carrying the last value of each block into the next one
+        for block_nr in Self::pos_block(pos)..=Self::pos_block(self.items - 1) {
+            self.decompress_block(block_nr, &mut block).unwrap();
+
+            let overflow = block[255];
+            block.copy_within(start..255, start + 1);
+            block[start] = carry;
+            carry = overflow;
+            start = 0;
+
+            let len = (self.block_len(block_nr) + 1).min(256);
+            self.write_block(block_nr, &block[..len]);
+        }
+
+        if Self::pos_in_block(self.items) == 0 {
+            // The last block was full so the carried value needs a new block
+            self.push(carry);
+        } else {
+            self.items += 1;
+        }
+    }
+
+    /// Appends all values of `slice` to the vector. This compresses whole chunks of the slice at
+    /// once and should be preferred over `extend` if the values are already in a slice.
+    pub fn extend_from_slice(&mut self, slice: &[u32]) {
+        self.invalidate_cache();
+        self.assert_sorted_append(slice.iter().copied());
+        self.flush_tail();
+
+        // Fill up the current block
+        let free = (256 - Self::pos_in_block(self.items)) % 256;
+        let (head, rest) = slice.split_at(free.min(slice.len()));
+
+        if !head.is_empty() {
+            let block_nr = self.last_block();
+            let start = Self::pos_in_block(self.items);
+
+            let mut block = vec![0u32; BitPacker8x::BLOCK_LEN];
+            self.decompress_block(block_nr, &mut block).unwrap();
+            block[start..start + head.len()].copy_from_slice(head);
+
+            self.write_block(block_nr, &block[..start + head.len()]);
+            self.items += head.len();
+        }
+
+        for chunk in rest.chunks(BitPacker8x::BLOCK_LEN) {
+            self.append_block(chunk);
+        }
+    }
+
+    /// Moves all values of `other` to the end of the vector, leaving `other` empty. If the vector
+    /// ends at a block boundary, the blocks of `other` get moved without recompression. Otherwise
+    /// all values of `other` have to be shifted and get pushed one by one.
+    pub fn append(&mut self, other: &mut CVec) {
+        self.invalidate_cache();
+        other.invalidate_cache();
+        self.assert_sorted_append(other.iter());
+        self.flush_tail();
+        other.flush_tail();
+
+        if Self::pos_in_block(self.items) == 0 {
+            // Drop preallocated blocks so the moved blocks directly follow the used ones
+            self.data.truncate(Self::pos_block(self.items));
+            self.bounds.truncate(self.data.len());
+
+            let blocks = Self::req_block_count(other.items);
+            self.data.extend(other.data.drain(..blocks));
+            self.bounds.extend(other.bounds.drain(..blocks));
+            self.items += other.items;
+        } else {
+            self.extend(other.iter());
+        }
+
+        other.clear();
+    }
+
+    /// Concatenates all `vecs` into a new vector like `slice::concat`, using the encoding settings
+    /// of the first one. Like `append`, the blocks of a vector which starts at a block boundary get
+    /// copied without recompression. Values following a partially filled block have to be shifted
+    /// and get recompressed.
+    pub fn concat(vecs: &[CVec]) -> CVec {
+        let mut new = vecs.first().map_or_else(CVec::new, |vec| vec.empty_like());
+
+        for vec in vecs {
+            new.assert_sorted_append(vec.iter());
+            if Self::pos_in_block(new.items) == 0 {
+                new.data.truncate(Self::pos_block(new.items));
+                new.bounds.truncate(new.data.len());
+
+                new.data.extend(vec.used_blocks().map(Cow::into_owned));
+                new.bounds
+                    .extend_from_slice(&vec.bounds[..Self::req_block_count(vec.items)]);
+                new.items += vec.items;
+            } else {
+                new.extend(vec.iter());
+            }
+        }
+
+        new
+    }
+
+    /// Pops the last element from the vector. Returns `None` if vector is empty or Some(val)
+    /// with the popped value. Emptied blocks stay allocated until `shrink_to_fit` gets called.
+    pub fn pop(&mut self) -> Option<u32> {
+        self.invalidate_cache();
+        if self.is_empty() {
+            return None;
+        }
+
+        let popped = match self.tail.pop() {
+            Some(popped) => popped,
+            None => self.last_unchecked()?,
+        };
+
+        self.items -= 1;
+
+        self.clear_empty_block();
+
+        Some(popped)
+    }
+
+    /// Removes the value at `pos` and replaces it with the last value like `Vec::swap_remove`. This
+    /// doesn't preserve the order but only touches the block of `pos` and the last block.
+    ///
+    /// # Panics
+    /// Panics if `pos >= self.len()`
+    pub fn swap_remove(&mut self, pos: usize) -> u32 {
+        self.invalidate_cache();
+        assert!(
+            pos < self.items,
+            "swap_remove index (is {}) should be < len (is {})",
+            pos,
+            self.items
+        );
+
+        let last = self.pop().unwrap();
+        if pos == self.items {
+            return last;
+        }
+        self.sorted = false;
+
+        let block_nr = Self::pos_block(pos);
+        let bounds = &mut self.bounds[block_nr];
+        *bounds = (bounds.0.min(last), bounds.1.max(last));
+
+        if self.is_staged(block_nr) {
+            return mem::replace(&mut self.tail[Self::pos_in_block(pos)], last);
+        }
+
+        let mut block = vec![0u32; BitPacker8x::BLOCK_LEN];
+        self.decompress_block(block_nr, &mut block).unwrap();
+        let removed = mem::replace(&mut block[Self::pos_in_block(pos)], last);
+        self.write_block(block_nr, &block[..self.block_len(block_nr)]);
+
+        removed
+    }
+
+    /// Splits the vector at `at` like `Vec::split_off`. Returns a new vector holding all values
+    /// from `at` on while `self` keeps the values before. If `at` is at a block boundary, the
+    /// blocks get moved without recompression.
+    ///
+    /// # Panics
+    /// Panics if `at > self.len()`
+    pub fn split_off(&mut self, at: usize) -> CVec {
+        self.invalidate_cache();
+        assert!(
+            at <= self.items,
+            "`at` split index (is {}) should be <= len (is {})",
+            at,
+            self.items
+        );
+
+        if Self::pos_in_block(at) != 0 {
+            let other = self.clone_range(at..);
+            self.truncate(at);
+            return other;
+        }
+
+        self.flush_tail();
+
+        let blocks = Self::pos_block(at)..Self::req_block_count(self.items);
+        let mut other = self.empty_like();
+        other.data = self.data.drain(blocks.clone()).collect();
+        other.bounds = self.bounds.drain(blocks).collect();
+        other.items = self.items - at;
+        self.items = at;
+        other
+    }
+
+    /// Removes the values in `range` from the vector and returns them as an iterator like
+    /// `Vec::drain`. The values get removed when the iterator gets dropped, even if it wasn't
+    /// fully consumed.
+    ///
+    /// # Panics
+    /// Panics if the range is out of bounds
+    #[inline]
+    pub fn drain<R: RangeBounds<usize>>(&mut self, range: R) -> Drain<'_> {
+        let (start, end) = self.resolve_range(range);
+        Drain::new(self, start, end)
+    }
+
+    /// Shortens the vector to the first `len` values and drops all blocks which aren't needed
+    /// anymore. Does nothing if `len >= self.len()`.
+    pub fn truncate(&mut self, len: usize) {
+        self.invalidate_cache();
+        if len >= self.items {
+            return;
+        }
+
+        self.flush_tail();
+        self.data.truncate(Self::req_block_count(len));
+        self.bounds.truncate(self.data.len());
+        self.items = len;
+
+        // Recompress the last block without the dropped values so its bitsize can shrink
+        let in_block = Self::pos_in_block(len);
+        if in_block != 0 {
+            let block_nr = Self::pos_block(len);
+            let mut block = vec![0u32; BitPacker8x::BLOCK_LEN];
+            self.decompress_block(block_nr, &mut block).unwrap();
+            self.write_block(block_nr, &block[..in_block]);
+        }
+    }
+
+    /// Removes all values from the vector. All blocks get dropped, so `capacity()` is 0 afterwards.
+    /// Use `clear_keep_capacity` to keep the blocks allocated for reuse.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.invalidate_cache();
+        self.data.clear();
+        self.bounds.clear();
+        self.tail.clear();
+        self.items = 0;
+    }
+
+    /// Removes all values from the vector but keeps all blocks and their buffers allocated, so
+    /// following pushes don't need to reallocate.
+    pub fn clear_keep_capacity(&mut self) {
+        self.invalidate_cache();
+        self.tail.clear();
+        for block in self.data.iter_mut() {
+            block.0 = 0;
+            block.1.clear();
+        }
+        self.items = 0;
+    }
+
+    /// Removes the value at `pos` and shifts all following values to the left.
+    ///
+    /// # Panics
+    /// Panics if `pos >= self.len()`
+    pub fn remove(&mut self, pos: usize) -> u32 {
+        self.invalidate_cache();
+        assert!(
+            pos < self.items,
+            "removal index (is {}) should be < len (is {})",
+            pos,
+            self.items
+        );
+
+        self.flush_tail();
+
+        let last_block = Self::pos_block(self.items - 1);
+        let mut block_nr = Self::pos_block(pos);
+        let mut start = Self::pos_in_block(pos);
+
+        let mut block = vec![0u32; BitPacker8x::BLOCK_LEN];
+        let mut next = vec![0u32; BitPacker8x::BLOCK_LEN];
+        self.decompress_block(block_nr, &mut block).unwrap();
+        let removed = block[start];
+
+        // Shift all values by one, pulling in the first value of the following block
+        loop {
+            block.copy_within(start + 1.., start);
+
+            if block_nr == last_block {
+                let len = self.block_len(block_nr) - 1;
+                self.write_block(block_nr, &block[..len]);
+                break;
+            }
+
+            self.decompress_block(block_nr + 1, &mut next).unwrap();
+            block[255] = next[0];
+            self.write_block(block_nr, &block);
+
+            mem::swap(&mut block, &mut next);
+            block_nr += 1;
+            start = 0;
+        }
+
+        self.items -= 1;
+        self.clear_empty_block();
+
+        removed
+    }
+
+    /// Returns the first number in the vector. `None` if `self.len() == 0`
+    #[inline]
+    pub fn first(&self) -> Option<u32> {
+        if self.is_empty() {
+            return None;
+        }
+
+        self.get(0)
+    }
+
+    /// Returns the last number in the vector. `None` if `self.len() == 0`
+    #[inline]
+    pub fn last(&self) -> Option<u32> {
+        if self.is_empty() {
+            return None;
+        }
+
+        self.last_unchecked()
+    }
+
+    /// Returns the value `n` positions before the end of the vector, so `get_back(0)` returns
+    /// the last value. `None` if `n >= self.len()`
+    #[inline]
+    pub fn get_back(&self, n: usize) -> Option<u32> {
+        let pos = self.items.checked_sub(n.checked_add(1)?)?;
+        self.get(pos)
+    }
+
+    /// Returns the u32 at `pos`
+    pub fn get(&self, pos: usize) -> Option<u32> {
+        if pos >= self.items {
+            return None;
+        }
+
+        Some(self.read_value(pos))
+    }
+
+    /// Returns the u32 at `pos` like `get`, but decompresses its block into `scratch`, which gets
+    /// resized if it's too small. Reusing the same buffer avoids any allocation for reads.
+    pub fn get_into(&self, pos: usize, scratch: &mut Vec<u32>) -> Option<u32> {
+        if pos >= self.items {
+            return None;
+        }
+
+        self.decompress_block(Self::pos_block(pos), scratch)?;
+        Some(scratch[Self::pos_in_block(pos)])
+    }
+
+    /// Returns the u32 at `pos` without checking whether `pos` is in bounds. Neither the position
+    /// nor the block it lies in get checked.
+    ///
+    /// # Safety
+    /// `pos` has to be smaller than `self.len()`. Calling this method with a position out of
+    /// bounds is undefined behavior, even if the result isn't used.
+    #[inline]
+    pub unsafe fn get_unchecked(&self, pos: usize) -> u32 {
+        debug_assert!(pos < self.items, "index {} out of bounds", pos);
+
+        let block_nr = Self::pos_block(pos);
+        if self.is_staged(block_nr) {
+            return *self.tail.get_unchecked(Self::pos_in_block(pos));
+        }
+
+        SCRATCH.with(|scratch| {
+            let mut scratch = scratch.borrow_mut();
+            let (header, data) = self.data.get_unchecked(block_nr);
+            Self::decompress(data, *header, &mut scratch);
+            // `decompress` fills at least a whole block
+            *scratch.get_unchecked(Self::pos_in_block(pos))
+        })
+    }
+
+    /// Returns the u32 at `pos`
+    pub fn set(&mut self, pos: usize, new: u32) -> Option<()> {
+        self.invalidate_cache();
+        if pos >= self.items {
+            return None;
+        }
+        self.sorted = false;
+
+        let block_nr = Self::pos_block(pos);
+        if self.is_staged(block_nr) {
+            self.tail[Self::pos_in_block(pos)] = new;
+            let bounds = &mut self.bounds[block_nr];
+            *bounds = (bounds.0.min(new), bounds.1.max(new));
+            return Some(());
+        }
+
+        let mut decompressed = vec![0u32; BitPacker8x::BLOCK_LEN];
+        self.decompress_block(block_nr, &mut decompressed)?;
+        *decompressed.get_mut(Self::pos_in_block(pos))? = new;
+        self.write_block(block_nr, &decompressed[..self.block_len(block_nr)]);
+
+        Some(())
+    }
+
+    /// Swaps the values at `a` and `b`. If both are in the same block, it only gets decompressed
+    /// and compressed once.
+    ///
+    /// # Panics
+    /// Panics if `a` or `b` are out of bounds
+    pub fn swap(&mut self, a: usize, b: usize) {
+        self.invalidate_cache();
+        assert!(
+            a < self.items && b < self.items,
+            "swap indices (are {} and {}) should be < len (is {})",
+            a,
+            b,
+            self.items
+        );
+        self.sorted = false;
+
+        let block_nr = Self::pos_block(a);
+        if block_nr != Self::pos_block(b) {
+            let val_a = self.get(a).unwrap();
+            let val_b = self.get(b).unwrap();
+            self.set(a, val_b).unwrap();
+            self.set(b, val_a).unwrap();
+            return;
+        }
+
+        let (a, b) = (Self::pos_in_block(a), Self::pos_in_block(b));
+        if self.is_staged(block_nr) {
+            self.tail.swap(a, b);
+            return;
+        }
+
+        let mut block = vec![0u32; BitPacker8x::BLOCK_LEN];
+        self.decompress_block(block_nr, &mut block).unwrap();
+        block.swap(a, b);
+        self.write_block(block_nr, &block[..self.block_len(block_nr)]);
+    }
+
+    /// Reverses the order of the values in place. If the last block is full, the blocks only get
+    /// reordered and reversed one by one. Otherwise the values have to be shifted between blocks,
+    /// so the vector gets rebuilt block by block without decompressing it as a whole.
+    pub fn reverse(&mut self) {
+        self.invalidate_cache();
+        self.sorted = false;
+        self.flush_tail();
+
+        let blocks = Self::req_block_count(self.items);
+        let mut block = vec![0u32; BitPacker8x::BLOCK_LEN];
+
+        if Self::pos_in_block(self.items) == 0 {
+            self.data[..blocks].reverse();
+            self.bounds[..blocks].reverse();
+
+            for block_nr in 0..blocks {
+                self.decompress_block(block_nr, &mut block).unwrap();
+                block.reverse();
+                self.write_block(block_nr, &block);
+            }
+            return;
+        }
+
+        let mut reversed = self.empty_like();
+        for block_nr in (0..blocks).rev() {
+            self.decompress_block(block_nr, &mut block).unwrap();
+            let values = &mut block[..self.block_len(block_nr)];
+            values.reverse();
+            reversed.extend_from_slice(values);
+        }
+
+        *self = reversed;
+    }
+
+    /// Rotates the vector in place so the value at `mid` becomes the first one, like
+    /// `slice::rotate_left`. If both `mid` and the length are multiples of the block size, only
+    /// the blocks get reordered. Otherwise the values get decompressed into a `Vec<u32>`, rotated
+    /// and compressed again.
+    ///
+    /// # Panics
+    /// Panics if `mid > self.len()`
+    pub fn rotate_left(&mut self, mid: usize) {
+        self.invalidate_cache();
+        assert!(
+            mid <= self.items,
+            "rotation index (is {}) should be <= len (is {})",
+            mid,
+            self.items
+        );
+        self.sorted = false;
+
+        if mid == 0 || mid == self.items {
+            return;
+        }
+
+        self.flush_tail();
+
+        if Self::pos_in_block(mid) == 0 && Self::pos_in_block(self.items) == 0 {
+            let blocks = Self::req_block_count(self.items);
+            self.data[..blocks].rotate_left(Self::pos_block(mid));
+            self.bounds[..blocks].rotate_left(Self::pos_block(mid));
+            return;
+        }
+
+        let mut values = self.as_vec();
+        values.rotate_left(mid);
+
+        let mut rotated = self.empty_like();
+        rotated.extend_from_slice(&values);
+        *self = rotated;
+    }
+
+    /// Rotates the vector in place so the last `k` values become the first ones, like
+    /// `slice::rotate_right`. See `rotate_left` for the cost.
+    ///
+    /// # Panics
+    /// Panics if `k > self.len()`
+    pub fn rotate_right(&mut self, k: usize) {
+        assert!(
+            k <= self.items,
+            "rotation amount (is {}) should be <= len (is {})",
+            k,
+            self.items
+        );
+
+        self.rotate_left(self.items - k);
+    }
+
+    /// Sorts the vector like `slice::sort_unstable`. The values get decompressed into a
+    /// `Vec<u32>` for sorting and compressed again afterwards.
+    pub fn sort_unstable(&mut self) {
+        self.invalidate_cache();
+        let mut values = self.as_vec();
+        values.sort_unstable();
+
+        let mut sorted = self.empty_like();
+        sorted.extend_from_slice(&values);
+        *self = sorted;
+    }
+
+    /// Sorts the vector and switches it into the sorted mode of `new_sorted`, so the blocks get
+    /// stored delta encoded and pushed values have to keep the vector sorted. Sorted data
+    /// usually compresses a lot better this way.
+    pub fn sort_unstable_delta(&mut self) {
+        self.invalidate_cache();
+        self.encoding = Encoding::Delta;
+        self.sorted = true;
+        self.sort_unstable();
+    }
+
+    /// Sets multiple values at once. Updates get grouped by their block so every affected block
+    /// only gets decompressed and compressed once. If a position occurs multiple times, the last
+    /// update wins. Returns `None` without changing anything if any position is out of bounds.
+    pub fn set_many(&mut self, updates: &[(usize, u32)]) -> Option<()> {
+        self.invalidate_cache();
+        if updates.iter().any(|(pos, _)| *pos >= self.items) {
+            return None;
+        }
+        self.sorted = false;
+
+        self.flush_tail();
+
+        // Stable sort keeps the order of updates within a block
+        let mut sorted = updates.to_vec();
+        sorted.sort_by_key(|(pos, _)| Self::pos_block(*pos));
+
+        let mut block = vec![0u32; BitPacker8x::BLOCK_LEN];
+        let mut start = 0;
+        while start < sorted.len() {
+            let block_nr = Self::pos_block(sorted[start].0);
+            let end = start
+                + sorted[start..]
+                    .iter()
+                    .take_while(|(pos, _)| Self::pos_block(*pos) == block_nr)
+                    .count();
+
+            self.decompress_block(block_nr, &mut block)?;
+
+            for (pos, val) in &sorted[start..end] {
+                block[Self::pos_in_block(*pos)] = *val;
+            }
+
+            self.write_block(block_nr, &block[..self.block_len(block_nr)]);
+            start = end;
+        }
+
+        Some(())
+    }
+
+    /// Copies the values within `range` into a new vector. Blocks fully covered by the range get
+    /// copied without recompression if `range` starts at a block boundary.
+    ///
+    /// # Panics
+    /// Panics if `range` is out of bounds
+    pub fn clone_range<R: RangeBounds<usize>>(&self, range: R) -> CVec {
+        let (start, end) = self.resolve_range(range);
+
+        if Self::pos_in_block(start) != 0 {
+            // All values have to be shifted within their blocks
+            let mut buf = BufCVecRef::new(self);
+            let mut new = self.empty_like();
+            new.extend((start..end).map(|i| *buf.get_buffered(i).unwrap()));
+            return new;
+        }
+
+        let first_block = Self::pos_block(start);
+        let last_block = Self::pos_block(end).max(first_block);
+        let mut new = CVec {
+            data: self.data[first_block..last_block].to_vec(),
+            bounds: self.bounds[first_block..last_block].to_vec(),
+            items: (last_block - first_block) * 256,
+            ..self.empty_like()
+        };
+
+        if Self::pos_in_block(end) != 0 {
+            let mut block = vec![0u32; BitPacker8x::BLOCK_LEN];
+            self.decompress_block(last_block, &mut block).unwrap();
+            new.extend(block[..Self::pos_in_block(end)].iter().copied());
+        }
+
+        new
+    }
+
+    /// Returns an iterator over the decompressed blocks of the vector. Each block gets decompressed
+    /// when it's reached and holds 256 values, except the last one which only holds the remaining
+    /// values.
+    pub fn blocks(&self) -> impl Iterator<Item = Vec<u32>> + '_ {
+        (0..Self::req_block_count(self.items)).map(move |block_nr| {
+            let mut block = vec![0u32; BitPacker8x::BLOCK_LEN];
+            self.decompress_block(block_nr, &mut block).unwrap();
+            block.truncate(self.block_len(block_nr));
+            block
+        })
+    }
+
+    /// Returns an iterator over chunks of `size` values like `slice::chunks`. The last chunk holds
+    /// the remaining values if the length isn't divisible by `size`. All chunks are read through
+    /// one buffered iterator, so each block only gets decompressed once.
+    ///
+    /// # Panics
+    /// Panics if `size` is 0
+    pub fn chunks(&self, size: usize) -> impl Iterator<Item = Vec<u32>> + '_ {
+        assert!(size != 0, "chunk size must be non-zero");
+
+        let mut iter = self.iter();
+        std::iter::from_fn(move || {
+            let chunk = iter.by_ref().take(size).collect::<Vec<_>>();
+            if chunk.is_empty() {
+                None
+            } else {
+                Some(chunk)
+            }
+        })
+    }
+
+    /// Returns an iterator over all overlapping windows of `size` values like `slice::windows`.
+    /// Windows span block boundaries, so the values get read through one buffered iterator into a
+    /// ring buffer holding the current window.
+    ///
+    /// # Panics
+    /// Panics if `size` is 0
+    pub fn windows(&self, size: usize) -> impl Iterator<Item = Vec<u32>> + '_ {
+        assert!(size != 0, "window size must be non-zero");
+
+        let mut iter = self.iter();
+        let mut window: VecDeque<u32> = iter.by_ref().take(size - 1).collect();
+        iter.map(move |val| {
+            if window.len() == size {
+                window.pop_front();
+            }
+            window.push_back(val);
+            window.iter().copied().collect()
+        })
+    }
+
+    /// Returns the values within `range` decompressed. Each spanned block only gets decompressed
+    /// once. The range gets clamped to the length of the vector, so only the values within bounds
+    /// get returned.
+    pub fn get_range<R: RangeBounds<usize>>(&self, range: R) -> Vec<u32> {
+        let (start, end) = self.clamp_range(range);
+        if start == end {
+            return Vec::new();
+        }
+
+        let mut out = Vec::with_capacity(end - start);
+        let mut block = vec![0u32; BitPacker8x::BLOCK_LEN];
+
+        for block_nr in Self::pos_block(start)..=Self::pos_block(end - 1) {
+            self.decompress_block(block_nr, &mut block).unwrap();
+
+            let offset = block_nr * BitPacker8x::BLOCK_LEN;
+            let from = start.saturating_sub(offset);
+            let to = (end - offset).min(BitPacker8x::BLOCK_LEN);
+            out.extend_from_slice(&block[from..to]);
+        }
+
+        out
+    }
+
+    /// Returns the first value and a new vector holding all other values, or `None` if the
+    /// vector is empty.
+    #[inline]
+    pub fn split_first(&self) -> Option<(u32, CVec)> {
+        let first = self.get(0)?;
+        Some((first, self.clone_range(1..)))
+    }
+
+    /// Returns a new vector holding all but the last value and the last value, or `None` if the
+    /// vector is empty. Full blocks get copied without recompression.
+    #[inline]
+    pub fn split_last(&self) -> Option<(CVec, u32)> {
+        let last = self.last()?;
+        Some((self.clone_range(..self.items - 1), last))
+    }
+
+    /// Returns an referenced iterator over the vector's elements
+    #[inline]
+    pub fn iter<'a>(&'a self) -> CVecIterRef<'a> {
+        CVecIterRef::new(self)
+    }
+
+    /// Returns a referenced iterator over the vector's elements starting at `start`. Unlike
+    /// `iter().skip(start)` this doesn't touch any of the skipped blocks. Yields nothing if `start`
+    /// is out of bounds.
+    #[inline]
+    pub fn iter_from(&self, start: usize) -> CVecIterRef<'_> {
+        CVecIterRef::with_start(self, start)
+    }
+
+    /// Returns a lending iterator over the vector's elements which yields references like a
+    /// slice iterator
+    #[inline]
+    pub fn iter_refs<'a>(&'a self) -> CVecLendingIter<'a> {
+        CVecLendingIter::new(self)
+    }
+
+    /// Returns a vector holding the maximum of each window of `window` consecutive values.
+    ///
+    /// # Panics
+    /// Panics if `window` is 0
+    #[inline]
+    pub fn rolling_max(&self, window: usize) -> CVec {
+        self.rolling(window, |new, old| new >= old)
+    }
+
+    /// Returns a vector holding the minimum of each window of `window` consecutive values.
+    ///
+    /// # Panics
+    /// Panics if `window` is 0
+    #[inline]
+    pub fn rolling_min(&self, window: usize) -> CVec {
+        self.rolling(window, |new, old| new <= old)
+    }
+
+    /// Computes a rolling extremum in O(n) using a monotonic deque. `replaces` returns `true` if
+    /// the new value makes an older value irrelevant for all following windows.
+    fn rolling<F: Fn(u32, u32) -> bool>(&self, window: usize, replaces: F) -> CVec {
+        assert!(window != 0, "window size must be non-zero");
+
+        let mut deque: VecDeque<(usize, u32)> = VecDeque::with_capacity(window);
+        let mut out = CVec::new();
+
+        for (pos, val) in self.iter().enumerate() {
+            while deque.back().is_some_and(|i| replaces(val, i.1)) {
+                deque.pop_back();
+            }
+            deque.push_back((pos, val));
+
+            if deque.front().unwrap().0 + window <= pos {
+                deque.pop_front();
+            }
+
+            if pos + 1 >= window {
+                out.push(deque.front().unwrap().1);
+            }
+        }
+
+        out
+    }
+
+    /// Keeps only the values for which `f` returns `true` like `Vec::retain`. Since removing values
+    /// shifts all following ones, the whole vector gets decompressed and rebuilt from the kept
+    /// values, which costs about as much as building it from scratch.
+    pub fn retain<F: FnMut(u32) -> bool>(&mut self, mut f: F) {
+        let mut retained = self.empty_like();
+        retained.extend(self.iter().filter(|val| f(*val)));
+        *self = retained;
+    }
+
+    /// Removes consecutive repeated values like `Vec::dedup`. The vector gets rebuilt from the
+    /// kept values.
     #[inline]
-    pub fn new() -> Self {
-        Self {
-            data: Vec::new(),
-            items: 0,
+    pub fn dedup(&mut self) {
+        self.dedup_by(|a, b| a == b)
+    }
+
+    /// Removes consecutive values which map to the same key, keeping the first one of each run.
+    /// Works like `Vec::dedup_by_key`.
+    #[inline]
+    pub fn dedup_by_key<K: PartialEq, F: FnMut(u32) -> K>(&mut self, mut key: F) {
+        self.dedup_by(|a, b| key(a) == key(b))
+    }
+
+    /// Removes consecutive values for which `same_bucket(value, previous)` returns `true`, where
+    /// `previous` is the last value that was kept. Works like `Vec::dedup_by`. The vector gets
+    /// rebuilt from the kept values.
+    pub fn dedup_by<F: FnMut(u32, u32) -> bool>(&mut self, mut same_bucket: F) {
+        let mut last: Option<u32> = None;
+
+        let mut deduped = self.empty_like();
+        deduped.extend(self.iter().filter(|val| {
+            if let Some(prev) = last {
+                if same_bucket(*val, prev) {
+                    return false;
+                }
+            }
+            last = Some(*val);
+            true
+        }));
+
+        *self = deduped;
+    }
+
+    /// Formats every value of the vector in the given `radix`, eg. for hex or binary dumps.
+    ///
+    /// # Panics
+    /// Panics if `radix` is not in `2..=36`
+    pub fn to_strings_radix(&self, radix: u32) -> Vec<String> {
+        assert!(
+            (2..=36).contains(&radix),
+            "radix must be in 2..=36, got {}",
+            radix
+        );
+
+        self.iter()
+            .map(|mut val| {
+                if val == 0 {
+                    return "0".to_string();
+                }
+
+                let mut digits = Vec::new();
+                while val > 0 {
+                    digits.push(std::char::from_digit(val % radix, radix).unwrap());
+                    val /= radix;
+                }
+                digits.iter().rev().collect()
+            })
+            .collect()
+    }
+
+    /// Returns `true` if the vector contains `val`. Blocks get decompressed and scanned one at a
+    /// time, skipping plain blocks whose bitsize is too small to hold `val`.
+    pub fn contains(&self, val: u32) -> bool {
+        let mut block = vec![0u32; BitPacker8x::BLOCK_LEN];
+
+        (0..Self::req_block_count(self.items)).any(|block_nr| {
+            let header = self.data[block_nr].0;
+            if !self.is_staged(block_nr)
+                && header & ENCODING_MASK == 0
+                && u32::BITS - val.leading_zeros() > header as u32
+            {
+                return false;
+            }
+
+            self.decompress_block(block_nr, &mut block).unwrap();
+            block[..self.block_len(block_nr)].contains(&val)
+        })
+    }
+
+    /// Counts the values matching the predicate `f`. Blocks get decompressed one after another into
+    /// a single buffer, so no values get materialized beyond one block.
+    pub fn count_matching<F: Fn(u32) -> bool>(&self, f: F) -> usize {
+        let mut block = vec![0u32; BitPacker8x::BLOCK_LEN];
+
+        (0..Self::req_block_count(self.items))
+            .map(|block_nr| {
+                self.decompress_block(block_nr, &mut block).unwrap();
+                block[..self.block_len(block_nr)]
+                    .iter()
+                    .filter(|i| f(**i))
+                    .count()
+            })
+            .sum()
+    }
+
+    /// Returns the position of the first value matching the predicate `f`. Blocks get decompressed
+    /// one after another until a match is found.
+    pub fn position<F: Fn(u32) -> bool>(&self, f: F) -> Option<usize> {
+        let mut block = vec![0u32; BitPacker8x::BLOCK_LEN];
+
+        (0..Self::req_block_count(self.items)).find_map(|block_nr| {
+            self.decompress_block(block_nr, &mut block).unwrap();
+            block[..self.block_len(block_nr)]
+                .iter()
+                .position(|i| f(*i))
+                .map(|pos| block_nr * BitPacker8x::BLOCK_LEN + pos)
+        })
+    }
+
+    /// Returns the position of the last value matching the predicate `f`. Blocks get decompressed
+    /// from the last one backwards until a match is found.
+    pub fn rposition<F: Fn(u32) -> bool>(&self, f: F) -> Option<usize> {
+        let mut block = vec![0u32; BitPacker8x::BLOCK_LEN];
+
+        (0..Self::req_block_count(self.items))
+            .rev()
+            .find_map(|block_nr| {
+                self.decompress_block(block_nr, &mut block).unwrap();
+                block[..self.block_len(block_nr)]
+                    .iter()
+                    .rposition(|i| f(*i))
+                    .map(|pos| block_nr * BitPacker8x::BLOCK_LEN + pos)
+            })
+    }
+
+    /// Returns the sum of all values. The sum is computed as `u64` so it can't overflow for
+    /// vectors with less than 2^32 values.
+    pub fn sum(&self) -> u64 {
+        let mut block = vec![0u32; BitPacker8x::BLOCK_LEN];
+
+        (0..Self::req_block_count(self.items))
+            .map(|block_nr| {
+                self.decompress_block(block_nr, &mut block).unwrap();
+                block[..self.block_len(block_nr)]
+                    .iter()
+                    .map(|i| *i as u64)
+                    .sum::<u64>()
+            })
+            .sum()
+    }
+
+    /// Returns the smallest value or `None` if the vector is empty. Blocks which can't contain a
+    /// smaller value than the current minimum according to their cached bounds don't get
+    /// decompressed. Named `min_value` since `Ord::min` would shadow a `min` method.
+    pub fn min_value(&self) -> Option<u32> {
+        self.reduce_blocks(|bounds, res| bounds.0 >= res, u32::min)
+    }
+
+    /// Returns the biggest value or `None` if the vector is empty. Blocks which can't contain a
+    /// bigger value than the current maximum according to their cached bounds don't get
+    /// decompressed. Named `max_value` since `Ord::max` would shadow a `max` method.
+    pub fn max_value(&self) -> Option<u32> {
+        self.reduce_blocks(|bounds, res| bounds.1 <= res, u32::max)
+    }
+
+    /// Returns true if the values are sorted in ascending order. Blocks get decompressed one after
+    /// another and the check stops at the first descending value.
+    pub fn is_sorted(&self) -> bool {
+        let mut block = vec![0u32; BitPacker8x::BLOCK_LEN];
+        let mut last = 0;
+
+        (0..Self::req_block_count(self.items)).all(|block_nr| {
+            self.decompress_block(block_nr, &mut block).unwrap();
+            let values = &block[..self.block_len(block_nr)];
+
+            let sorted = last <= values[0] && values.windows(2).all(|i| i[0] <= i[1]);
+            last = values[values.len() - 1];
+            sorted
+        })
+    }
+
+    /// Binary searches the sorted vector for `val` like `slice::binary_search`. Returns `Ok` with
+    /// the position of a matching value or `Err` with the position `val` could be inserted at
+    /// while keeping the vector sorted. Only the blocks probed by the search get decompressed.
+    pub fn binary_search(&self, val: u32) -> Result<usize, usize> {
+        let mut block = vec![0u32; BitPacker8x::BLOCK_LEN];
+
+        // Find the first block starting with a value bigger than `val`
+        let mut lo = 0;
+        let mut hi = Self::req_block_count(self.items);
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            if self.block_first(mid, &mut block) <= val {
+                lo = mid + 1;
+            } else {
+                hi = mid;
+            }
+        }
+
+        // `val` can only be in the block before
+        if lo == 0 {
+            return Err(0);
         }
+
+        let block_nr = lo - 1;
+        self.decompress_block(block_nr, &mut block).unwrap();
+        let offset = block_nr * BitPacker8x::BLOCK_LEN;
+        block[..self.block_len(block_nr)]
+            .binary_search(&val)
+            .map(|i| offset + i)
+            .map_err(|i| offset + i)
     }
 
-    /// Allocate a new compressed vector which can store `capacity` numbers without reallocating
-    pub fn with_capacity(capacity: usize) -> Self {
-        let req_blocks = Self::req_block_count(capacity);
+    /// Returns the position and value of all values within `lo..=hi`. Blocks which can't contain
+    /// such values according to their cached bounds get skipped without being decompressed.
+    pub fn range_scan(&self, lo: u32, hi: u32) -> impl Iterator<Item = (usize, u32)> + '_ {
+        let mut block = vec![0u32; BitPacker8x::BLOCK_LEN];
 
-        let data = (0..req_blocks)
-            .map(|_| (0, Vec::with_capacity(256)))
-            .collect();
+        self.blocks_in_range(lo, hi).flat_map(move |block_nr| {
+            self.decompress_block(block_nr, &mut block).unwrap();
+            let offset = block_nr * BitPacker8x::BLOCK_LEN;
+            block[..self.block_len(block_nr)]
+                .iter()
+                .enumerate()
+                .filter(|(_, val)| (lo..=hi).contains(*val))
+                .map(|(i, val)| (offset + i, *val))
+                .collect::<Vec<_>>()
+        })
+    }
 
-        Self { data, items: 0 }
+    /// Returns the indices of all blocks which may contain values within `lo..=hi`. These are
+    /// the blocks `range_scan` decompresses.
+    pub fn blocks_in_range(&self, lo: u32, hi: u32) -> impl Iterator<Item = usize> + '_ {
+        self.bounds[..Self::req_block_count(self.items)]
+            .iter()
+            .enumerate()
+            .filter(move |(_, (min, max))| lo <= hi && *min <= hi && *max >= lo)
+            .map(|(block_nr, _)| block_nr)
     }
 
-    /// Returns the amount of allocated bytes by the vector
+    /// Returns `true` if all values in the vector are 0. This is cheap since blocks which only
+    /// contain 0s are stored with a bitsize of 0. Can be used to avoid materializing vectors
+    /// like with `as_vec` if they only contain 0s.
+    pub fn is_all_zero(&self) -> bool {
+        self.used_blocks().enumerate().all(|(block_nr, block)| {
+            if block.0 == 0 {
+                return true;
+            }
+
+            // A partially filled block may hold stale values behind the end of the vector
+            let len = self.items - block_nr * 256;
+            if len >= 256 {
+                return false;
+            }
+
+            let mut decompressed = vec![0u32; BitPacker8x::BLOCK_LEN];
+            self.decompress_block(block_nr, &mut decompressed).unwrap();
+            decompressed[..len].iter().all(|i| *i == 0)
+        })
+    }
+
+    /// Returns the data hold by CVec decompressed as `Vec::<u32>`
     #[inline]
-    pub fn byte_len(&self) -> usize {
-        // `items` and initial `data` vec
-        let mut len = size_of::<usize>() * 2;
+    pub fn as_vec(&self) -> Vec<u32> {
+        Vec::from(self)
+    }
+
+    /// Returns the values converted into a smaller integer type like `u16` or `u8`. Fails with the
+    /// position of the first value which doesn't fit into `T`.
+    ///
+    /// This can't be a `TryFrom` implementation since it would conflict with the blanket
+    /// implementation for the existing `From<CVec>` conversions.
+    pub fn try_as_vec<T: TryFrom<u32>>(&self) -> Result<Vec<T>, ValueOverflowError> {
+        self.iter()
+            .enumerate()
+            .map(|(pos, value)| T::try_from(value).map_err(|_| ValueOverflowError { pos, value }))
+            .collect()
+    }
 
-        for block in self.data.iter() {
-            // u8 size
-            len += 1;
-            // block  size
-            len += block.1.len();
+    /// Decompresses the whole vector once and returns the values as slice. The decompressed values
+    /// are kept until the vector gets modified, so repeated calls are free. Note that this holds
+    /// all values uncompressed next to the compressed blocks, so the vector needs more memory than
+    /// a plain `Vec<u32>` as long as the cache exists. `shrink_to_fit` releases it early.
+    pub fn as_slice_cached(&mut self) -> &[u32] {
+        if self.cache.is_none() {
+            self.cache = Some(self.as_vec());
         }
+        self.cache.as_deref().unwrap()
+    }
 
-        len
+    /// Checks the internal invariants of the vector in debug builds. Intended for tests.
+    ///
+    /// # Panics
+    /// Panics if the vector is in an inconsistent state
+    #[doc(hidden)]
+    pub fn debug_assert_valid(&self) {
+        debug_assert!(
+            self.data.len() >= Self::req_block_count(self.items),
+            "{} values don't fit into {} blocks",
+            self.items,
+            self.data.len()
+        );
+
+        debug_assert!(
+            self.tail.is_empty() || self.tail.len() == Self::pos_in_block(self.items),
+            "{} staged values for {} values",
+            self.tail.len(),
+            self.items
+        );
+
+        debug_assert_eq!(self.bounds.len(), self.data.len(), "bounds out of sync");
+
+        let mut block = vec![0u32; BitPacker8x::BLOCK_LEN];
+        for block_nr in 0..Self::req_block_count(self.items) {
+            let (min, max) = self.bounds[block_nr];
+            self.decompress_block(block_nr, &mut block).unwrap();
+            debug_assert!(
+                block[..self.block_len(block_nr)]
+                    .iter()
+                    .all(|i| (min..=max).contains(i)),
+                "values of block {} exceed its bounds",
+                block_nr
+            );
+        }
+
+        if let Some(cache) = &self.cache {
+            debug_assert!(self.iter().eq(cache.iter().copied()), "cache outdated");
+        }
+
+        for (header, block) in self.data.iter() {
+            let num_bits = header & BITS_MASK;
+            debug_assert!(num_bits <= 32, "invalid bitsize {}", num_bits);
+            debug_assert!(
+                block.len() >= Self::compressed_len(*header),
+                "block too short for header {:#010b}",
+                header
+            );
+        }
     }
 
-    /// Returns the number of elements in the vector
+    /// Returns the block `pos` is stored in
     #[inline]
-    pub fn len(&self) -> usize {
-        self.items
+    pub(crate) fn pos_block(pos: usize) -> usize {
+        pos / 256
     }
 
-    /// Returns true if the vector is empty
+    /// Returns the position of `pos` in a block
     #[inline]
-    pub fn is_empty(&self) -> bool {
-        self.len() == 0
+    pub(crate) fn pos_in_block(pos: usize) -> usize {
+        pos % 256
     }
 
-    /// Returns the number of numbers the vector can hold without reallocating
+    /// Returns the index in `self.data` of the last block
     #[inline]
-    pub fn capacity(&self) -> usize {
-        self.data.len() * 256
+    pub(crate) fn last_block(&self) -> usize {
+        Self::pos_block(self.items)
     }
 
-    /// Pushes a new value on top of the vector
-    pub fn push(&mut self, val: u32) {
-        if self.need_new_block() {
-            let mut new_block = Vec::with_capacity(256);
-            let num_bits = Self::compress(vec![val], &mut new_block);
-            self.data.push((num_bits, new_block));
-        } else {
-            let block_nr = self.last_block();
+    /// Returns the start (inclusive) and end (exclusive) of `range`.
+    ///
+    /// # Panics
+    /// Panics if `range` is out of bounds or its start is greater than its end
+    pub(crate) fn resolve_range<R: RangeBounds<usize>>(&self, range: R) -> (usize, usize) {
+        let (start, end) = self.range_bounds(range);
 
-            // decompress last block
-            let mut block = vec![0u32; BitPacker8x::BLOCK_LEN];
-            self.decompress_block(block_nr, &mut block).unwrap();
+        assert!(start <= end, "range start {} > end {}", start, end);
+        assert!(
+            end <= self.items,
+            "range end {} out of bounds for length {}",
+            end,
+            self.items
+        );
+
+        (start, end)
+    }
 
-            // Set value at position
-            block[self.items % 256] = val;
+    /// Returns the start (inclusive) and end (exclusive) of `range` clamped to the length of the
+    /// vector. The start is never greater than the end.
+    fn clamp_range<R: RangeBounds<usize>>(&self, range: R) -> (usize, usize) {
+        let (start, end) = self.range_bounds(range);
+        let end = end.min(self.items);
+        (start.min(end), end)
+    }
 
-            // If get_mut would return None, the if block was executed.
-            let mut out_block = self.data.get_mut(block_nr).unwrap();
+    /// Returns the start (inclusive) and end (exclusive) of `range` without checking them
+    #[inline]
+    fn range_bounds<R: RangeBounds<usize>>(&self, range: R) -> (usize, usize) {
+        let start = match range.start_bound() {
+            Bound::Included(start) => *start,
+            Bound::Excluded(start) => start.saturating_add(1),
+            Bound::Unbounded => 0,
+        };
 
-            // Compress block again
-            let bit_size = Self::compress(block, &mut out_block.1);
-            out_block.0 = bit_size;
+        let end = match range.end_bound() {
+            Bound::Included(end) => end.saturating_add(1),
+            Bound::Excluded(end) => *end,
+            Bound::Unbounded => self.items,
+        };
+
+        (start, end)
+    }
+
+    /// Compresses `values` into the next block, reusing a preallocated block if there is one.
+    /// Must only be called if the last block is full.
+    fn append_block(&mut self, values: &[u32]) {
+        debug_assert!(Self::pos_in_block(self.items) == 0);
+
+        let block_nr = self.last_block();
+
+        if block_nr >= self.data.len() {
+            // Every block gets its own buffer so no stale bytes get carried over
+            self.push_placeholder();
         }
+        self.write_block(block_nr, values);
 
-        self.items += 1;
+        // Only count the values once they are stored
+        self.items += values.len();
     }
 
-    /// Pops the last element from the vector. Returns `None` if vector is empty or Some(val)
-    /// with the popped value
-    pub fn pop(&mut self) -> Option<u32> {
-        if self.is_empty() {
-            return None;
+    /// Returns a new, empty vector with the same encoding settings as `self`
+    #[inline]
+    fn empty_like(&self) -> Self {
+        Self {
+            encoding: self.encoding,
+            sorted: self.sorted,
+            ..Self::new()
         }
+    }
 
-        let popped = self.last_unchecked()?;
+    /// Panics if `val` is smaller than `last`, the last value of a vector created with
+    /// `new_sorted`
+    #[inline]
+    fn assert_sorted_after(last: Option<u32>, val: u32) {
+        if let Some(last) = last {
+            assert!(
+                last <= val,
+                "pushed {} after {} into sorted CVec",
+                val,
+                last
+            );
+        }
+    }
 
-        self.items -= 1;
+    /// Panics if appending `values` breaks the order of a vector created with `new_sorted`. Gets
+    /// checked before any value is appended, so the vector stays unchanged.
+    fn assert_sorted_append<I: IntoIterator<Item = u32>>(&self, values: I) {
+        if !self.sorted {
+            return;
+        }
 
-        // Remove last allocated block if it gets empty
-        if self.items % 256 == 0 {
-            let block_nr = self.last_block();
-            self.data.remove(block_nr);
+        let mut last = self.last();
+        for val in values {
+            Self::assert_sorted_after(last, val);
+            last = Some(val);
         }
+    }
 
-        Some(popped)
+    /// Compresses `values` into the existing block `block_nr` using the vectors encoding
+    #[inline]
+    fn write_block(&mut self, block_nr: usize, values: &[u32]) {
+        let out_block = &mut self.data[block_nr];
+        out_block.0 = Self::compress(values, &mut out_block.1, self.encoding);
+        self.bounds[block_nr] = Self::min_max(values);
     }
 
-    /// Returns the last number in the vector. `None` if `self.len() == 0`
+    /// Appends an empty block which gets written later
     #[inline]
-    pub fn last(&self) -> Option<u32> {
-        if self.is_empty() {
-            return None;
+    fn push_placeholder(&mut self) {
+        self.data.push((0, Vec::new()));
+        self.bounds.push((0, 0));
+    }
+
+    /// Reduces all values with `f`, decompressing one block at a time. Blocks for which `skip`
+    /// returns true given their bounds and the current result don't get decompressed.
+    fn reduce_blocks<S, F>(&self, skip: S, f: F) -> Option<u32>
+    where
+        S: Fn((u32, u32), u32) -> bool,
+        F: Fn(u32, u32) -> u32,
+    {
+        let mut block = vec![0u32; BitPacker8x::BLOCK_LEN];
+        let mut res: Option<u32> = None;
+
+        for block_nr in 0..Self::req_block_count(self.items) {
+            if res.is_some_and(|res| skip(self.bounds[block_nr], res)) {
+                continue;
+            }
+
+            self.decompress_block(block_nr, &mut block).unwrap();
+            let reduced = block[..self.block_len(block_nr)].iter().copied().reduce(&f);
+            res = match (res, reduced) {
+                (Some(a), Some(b)) => Some(f(a, b)),
+                (a, b) => a.or(b),
+            };
         }
 
-        self.last_unchecked()
+        res
     }
 
-    /// Returns the u32 at `pos`
-    pub fn get(&self, pos: usize) -> Option<u32> {
-        if pos >= self.items {
-            return None;
+    /// Returns the minimum and maximum of `values`. For no values, the bounds don't contain any
+    /// value.
+    #[inline]
+    fn min_max(values: &[u32]) -> (u32, u32) {
+        values.iter().fold((u32::MAX, 0), |(min, max), val| {
+            (min.min(*val), max.max(*val))
+        })
+    }
+
+    /// Replaces all values of block `block_nr` with `values`, which has to hold as many values as
+    /// the block.
+    pub(crate) fn replace_block(&mut self, block_nr: usize, values: &[u32]) {
+        self.invalidate_cache();
+        self.sorted = false;
+        if self.is_staged(block_nr) {
+            self.tail.copy_from_slice(values);
+            self.bounds[block_nr] = Self::min_max(values);
+        } else {
+            self.write_block(block_nr, values);
         }
+    }
 
-        let mut decompressed = vec![0u32; BitPacker8x::BLOCK_LEN];
-        self.decompress_block(Self::pos_block(pos), &mut decompressed)?;
-        decompressed.get(Self::pos_in_block(pos)).map(|i| *i)
+    /// Returns the amount of values stored in block `block_nr`
+    #[inline]
+    pub(crate) fn block_len(&self, block_nr: usize) -> usize {
+        (self.items - block_nr * 256).min(256)
     }
 
-    /// Returns the u32 at `pos`
-    pub fn set(&mut self, pos: usize, new: u32) -> Option<()> {
-        if pos >= self.items {
-            return None;
+    /// Reads the value at `pos`, which has to be in bounds. Blocks get decompressed into the
+    /// thread local scratch buffer.
+    #[inline]
+    fn read_value(&self, pos: usize) -> u32 {
+        let block_nr = Self::pos_block(pos);
+        if self.is_staged(block_nr) {
+            return self.tail[Self::pos_in_block(pos)];
         }
 
-        let mut decompressed = vec![0u32; BitPacker8x::BLOCK_LEN];
-        self.decompress_block(Self::pos_block(pos), &mut decompressed)?;
-        *decompressed.get_mut(Self::pos_in_block(pos))? = new;
-        let bit_size = Self::compress(
-            decompressed,
-            &mut self.data.get_mut(Self::pos_block(pos)).unwrap().1,
-        );
-        self.data.get_mut(Self::pos_block(pos)).unwrap().0 = bit_size;
-
-        Some(())
+        SCRATCH.with(|scratch| {
+            let mut scratch = scratch.borrow_mut();
+            let (header, data) = &self.data[block_nr];
+            Self::decompress(data, *header, &mut scratch);
+            scratch[Self::pos_in_block(pos)]
+        })
     }
 
-    /// Returns an referenced iterator over the vector's elements
+    /// Drops the values cached by `as_slice_cached` since they're about to become outdated
     #[inline]
-    pub fn iter<'a>(&'a self) -> CVecIterRef<'a> {
-        CVecIterRef::new(self)
+    fn invalidate_cache(&mut self) {
+        self.cache = None;
     }
 
-    /// Returns the data hold by CVec decompressed as `Vec::<u32>`
+    /// Clears the last block if it doesn't hold any values anymore but keeps it allocated to
+    /// preserve the capacity.
     #[inline]
-    pub fn as_vec(&self) -> Vec<u32> {
-        Vec::from(self)
+    fn clear_empty_block(&mut self) {
+        if self.items % 256 == 0 {
+            let block_nr = self.last_block();
+            let block = &mut self.data[block_nr];
+            block.0 = 0;
+            block.1.clear();
+        }
     }
 
-    /// Returns the block `pos` is stored in
+    /// Returns all blocks which hold values, skipping preallocated ones
     #[inline]
-    pub(crate) fn pos_block(pos: usize) -> usize {
-        pos / 256
+    fn used_blocks(&self) -> impl Iterator<Item = Cow<'_, (u8, Vec<u8>)>> {
+        self.current_blocks()
+            .take(Self::req_block_count(self.items))
     }
 
-    /// Returns the position of `pos` in a block
+    /// Returns all blocks including preallocated ones. The staged last block gets compressed
+    /// on the fly.
+    fn current_blocks(&self) -> impl Iterator<Item = Cow<'_, (u8, Vec<u8>)>> {
+        self.data.iter().enumerate().map(move |(block_nr, block)| {
+            if !self.is_staged(block_nr) {
+                return Cow::Borrowed(block);
+            }
+
+            let mut out = Vec::new();
+            let header = Self::compress(&self.tail, &mut out, self.encoding);
+            Cow::Owned((header, out))
+        })
+    }
+
+    /// Returns the first value of block `block_nr`. Delta encoded blocks store it in front of
+    /// their data, all other blocks get decompressed into `buf`.
+    fn block_first(&self, block_nr: usize, buf: &mut Vec<u32>) -> u32 {
+        let (header, data) = &self.data[block_nr];
+        if !self.is_staged(block_nr) && header & ENCODING_MASK == DELTA_BLOCK {
+            return Self::read_reference(data);
+        }
+
+        self.decompress_block(block_nr, buf).unwrap();
+        buf[0]
+    }
+
+    /// Returns `true` if the values of block `block_nr` are held in `self.tail`
     #[inline]
-    pub(crate) fn pos_in_block(pos: usize) -> usize {
-        pos % 256
+    fn is_staged(&self, block_nr: usize) -> bool {
+        !self.tail.is_empty() && block_nr == Self::pos_block(self.items - 1)
     }
 
-    /// Returns the index in `self.data` of the last block
+    /// Returns the length of the compressed data of a block with the given header
     #[inline]
-    pub(crate) fn last_block(&self) -> usize {
-        Self::pos_block(self.items)
+    fn compressed_len(header: u8) -> usize {
+        let packed = (header & BITS_MASK) as usize * BitPacker8x::BLOCK_LEN / 8;
+
+        match header & ENCODING_MASK {
+            DELTA_BLOCK | FOR_BLOCK => REFERENCE_LEN + packed,
+            _ => packed,
+        }
     }
 
     /// Returns true if a new block needs to be allocated.
@@ -222,55 +2110,192 @@ impl CVec {
         self.get(self.len() - 1)
     }
 
-    /// Compresses a Vec<u32>
+    /// Compresses a slice of up to 256 u32 values into `out` the same way `CVec` compresses its
+    /// blocks. Returns the header of the compressed block which holds its bitsize and encoding
+    /// flags, or an error if `data` holds more than 256 values.
+    pub fn checked_compress(data: &[u32], out: &mut Vec<u8>) -> Result<u8, CompressError> {
+        if data.len() > BitPacker8x::BLOCK_LEN {
+            return Err(CompressError::TooManyValues(data.len()));
+        }
+
+        Ok(Self::compress(data, out, Encoding::Plain))
+    }
+
+    /// Compresses a slice of up to 256 u32 values. Shorter slices get padded with their last
+    /// value, which doesn't increase the bitsize for any encoding. Returns the header of the
+    /// compressed block which holds its bitsize and encoding.
     ///
     /// # Panics
-    /// Panics if data.len() > 256
-    fn compress(mut data: Vec<u32>, out: &mut Vec<u8>) -> u8 {
+    /// Panics if data.len() > 256. Use `checked_compress` for unchecked input.
+    fn compress(data: &[u32], out: &mut Vec<u8>, encoding: Encoding) -> u8 {
         assert!(data.len() <= 256);
 
-        if data.len() < 256 {
-            data.extend((0..(256 - data.len() as u32 % 256)).map(|_| 0));
+        let mut padded = [data.last().copied().unwrap_or(0); BitPacker8x::BLOCK_LEN];
+        let data = if data.len() < BitPacker8x::BLOCK_LEN {
+            padded[..data.len()].copy_from_slice(data);
+            &padded[..]
+        } else {
+            data
+        };
+
+        let bitpacker = Self::bitpacker();
+        let num_bits: u8 = bitpacker.num_bits(data);
+
+        // Only use the preferred encoding if it's smaller than the plain block
+        let plain_len = 32 * num_bits as usize;
+        match encoding {
+            Encoding::Delta if data.windows(2).all(|i| i[0] <= i[1]) => {
+                let delta_bits = bitpacker.num_bits_sorted(data[0], data);
+                if REFERENCE_LEN + 32 * (delta_bits as usize) < plain_len {
+                    out.resize(REFERENCE_LEN + 32 * delta_bits as usize, 0);
+                    out[..REFERENCE_LEN].copy_from_slice(&data[0].to_le_bytes());
+                    bitpacker.compress_sorted(data[0], data, &mut out[REFERENCE_LEN..], delta_bits);
+                    return delta_bits | DELTA_BLOCK;
+                }
+            }
+            Encoding::FrameOfReference => {
+                let min = *data.iter().min().unwrap();
+                let mut shifted = [0u32; BitPacker8x::BLOCK_LEN];
+                for (out, val) in shifted.iter_mut().zip(data) {
+                    *out = val - min;
+                }
+
+                let for_bits = bitpacker.num_bits(&shifted);
+                if REFERENCE_LEN + 32 * (for_bits as usize) < plain_len {
+                    out.resize(REFERENCE_LEN + 32 * for_bits as usize, 0);
+                    out[..REFERENCE_LEN].copy_from_slice(&min.to_le_bytes());
+                    bitpacker.compress(&shifted, &mut out[REFERENCE_LEN..], for_bits);
+                    return for_bits | FOR_BLOCK;
+                }
+            }
+            _ => (),
         }
 
-        let bitpacker = BitPacker8x::new();
-        let num_bits: u8 = bitpacker.num_bits(&data);
+        // Only blocks with the highest bit set can benefit from zigzag encoding
+        if num_bits == 32 {
+            let mut zigzag = [0u32; BitPacker8x::BLOCK_LEN];
+            for (out, val) in zigzag.iter_mut().zip(data) {
+                *out = Self::zigzag_encode(*val);
+            }
+
+            let zigzag_bits = bitpacker.num_bits(&zigzag);
+            if zigzag_bits < num_bits {
+                out.resize(32 * zigzag_bits as usize, 0);
+                bitpacker.compress(&zigzag, out, zigzag_bits);
+                return zigzag_bits | ZIGZAG_BLOCK;
+            }
+        }
 
-        let out_size = 32 * num_bits as usize;
-        out.resize(out_size, 0);
+        out.resize(plain_len, 0);
 
-        bitpacker.compress(&data, out, num_bits);
+        bitpacker.compress(data, out, num_bits);
         num_bits
     }
 
+    /// Maps a value interpreted as `i32` to an unsigned value so that values with a small
+    /// magnitude (positive or negative) require few bits
+    #[inline]
+    fn zigzag_encode(val: u32) -> u32 {
+        let val = val as i32;
+        ((val << 1) ^ (val >> 31)) as u32
+    }
+
+    /// Reverts `zigzag_encode`
+    #[inline]
+    fn zigzag_decode(val: u32) -> u32 {
+        (val >> 1) ^ (val & 1).wrapping_neg()
+    }
+
     /// Decompress a given block at `index`
     ///
     /// Returns `None` if there is no such block.
     #[inline]
     fn decompress_block(&self, index: usize, out: &mut Vec<u32>) -> Option<()> {
-        let (num_bits, block) = self.data.get(index)?;
-        Self::decompress(block, *num_bits, out);
+        if self.is_staged(index) {
+            if out.len() < BitPacker8x::BLOCK_LEN {
+                out.resize(BitPacker8x::BLOCK_LEN, 0);
+            }
+            out[..self.tail.len()].copy_from_slice(&self.tail);
+            return Some(());
+        }
+
+        let (header, block) = self.data.get(index)?;
+        Self::decompress(block, *header, out);
         Some(())
     }
 
-    /// Decompresses `data` and writes them to `out`. If `out` has an invalid size, it gets padded
-    /// with 0s.
+    /// Returns the bitpacker used for all blocks. `BitPacker8x::new` detects the supported
+    /// instruction set on every call, so the bitpacker only gets created once and copied after.
+    #[inline]
+    fn bitpacker() -> BitPacker8x {
+        static BITPACKER: OnceLock<BitPacker8x> = OnceLock::new();
+        *BITPACKER.get_or_init(BitPacker8x::new)
+    }
+
+    /// Decompresses `data` with the given block `header` and writes them to `out`. If `out` has an
+    /// invalid size, it gets padded with 0s.
     ///
     /// # Panics
     /// panics if `data` is too short
-    fn decompress(data: &[u8], num_bits: u8, out: &mut Vec<u32>) {
-        let bitpacker = BitPacker8x::new();
+    fn decompress(data: &[u8], header: u8, out: &mut Vec<u32>) {
+        let bitpacker = Self::bitpacker();
+        let num_bits = header & BITS_MASK;
 
         if out.len() < BitPacker8x::BLOCK_LEN {
             out.resize(BitPacker8x::BLOCK_LEN, 0);
         }
 
+        let out = &mut out[0..BitPacker8x::BLOCK_LEN];
+
+        // Empty and preallocated blocks have no packed data at all, so don't hand an empty slice
+        // to the bitpacker. All values of such blocks are equal to the blocks reference value.
+        if num_bits == 0 {
+            let val = match header & ENCODING_MASK {
+                DELTA_BLOCK | FOR_BLOCK => Self::read_reference(data),
+                _ => 0,
+            };
+            out.iter_mut().for_each(|i| *i = val);
+            return;
+        }
+
         let compressed_len = (num_bits as usize) * BitPacker8x::BLOCK_LEN / 8;
-        bitpacker.decompress(
-            &data[..compressed_len],
-            &mut out[0..BitPacker8x::BLOCK_LEN],
-            num_bits,
-        );
+
+        match header & ENCODING_MASK {
+            DELTA_BLOCK => {
+                let initial = Self::read_reference(data);
+                let data = &data[REFERENCE_LEN..REFERENCE_LEN + compressed_len];
+                bitpacker.decompress_sorted(initial, data, out, num_bits);
+            }
+            FOR_BLOCK => {
+                let min = Self::read_reference(data);
+                let data = &data[REFERENCE_LEN..REFERENCE_LEN + compressed_len];
+                bitpacker.decompress(data, out, num_bits);
+                // Crafted blocks can have a reference which overflows, which mustn't panic
+                out.iter_mut().for_each(|i| *i = i.wrapping_add(min));
+            }
+            ZIGZAG_BLOCK => {
+                bitpacker.decompress(&data[..compressed_len], out, num_bits);
+                out.iter_mut().for_each(|i| *i = Self::zigzag_decode(*i));
+            }
+            _ => {
+                bitpacker.decompress(&data[..compressed_len], out, num_bits);
+            }
+        }
+    }
+
+    /// Reads the reference value stored in front of a delta or frame of reference block
+    #[inline]
+    fn read_reference(data: &[u8]) -> u32 {
+        let mut bytes = [0u8; REFERENCE_LEN];
+        bytes.copy_from_slice(&data[..REFERENCE_LEN]);
+        u32::from_le_bytes(bytes)
+    }
+}
+
+impl<'a> Extend<&'a u32> for CVec {
+    #[inline]
+    fn extend<T: IntoIterator<Item = &'a u32>>(&mut self, iter: T) {
+        self.extend(iter.into_iter().copied())
     }
 }
 
@@ -278,7 +2303,16 @@ impl Extend<u32> for CVec {
     /// Reads all values from `iter` and pushes them onto the vector. This should be preferred over
     /// `push` if you have more than one value to append.
     fn extend<T: IntoIterator<Item = u32>>(&mut self, iter: T) {
-        let mut iter = iter.into_iter();
+        self.invalidate_cache();
+        let sorted = self.sorted;
+        let mut last = self.last();
+        let mut iter = iter.into_iter().inspect(move |val| {
+            if sorted {
+                Self::assert_sorted_after(last, *val);
+                last = Some(*val);
+            }
+        });
+        self.flush_tail();
 
         // How many items were pushed
         let mut pushed: usize = 0;
@@ -294,7 +2328,8 @@ impl Extend<u32> for CVec {
             let mut block = vec![0u32; BitPacker8x::BLOCK_LEN];
             self.decompress_block(last_block_idx, &mut block).unwrap();
 
-            // Set all values
+            // Set all values. `self` doesn't get touched until the block is complete, so a
+            // panicking iterator leaves the vector in a valid state.
             let start = self.items % 256;
             for i in start..start + to_fill {
                 block[i] = match iter.next() {
@@ -305,18 +2340,14 @@ impl Extend<u32> for CVec {
             }
 
             // Compress block again
-            let mut out_block = self.data.get_mut(last_block_idx).unwrap();
-            let bit_size = Self::compress(block, &mut out_block.1);
-            out_block.0 = bit_size;
+            self.write_block(last_block_idx, &block[..start + pushed]);
             self.items += pushed;
         }
 
-        // Push rest of `iter` into new block(s)
-        let mut block = Vec::new();
+        // Push rest of `iter` into preallocated or new block(s). Each chunk gets collected before
+        // it gets appended so a panic never leaves a partially written block behind.
         for to_add in iter.by_ref().chunked(256) {
-            self.items += to_add.len();
-            let num_bits = Self::compress(to_add, &mut block);
-            self.data.push((num_bits, block.clone()));
+            self.append_block(&to_add);
         }
     }
 }
@@ -0,0 +1,40 @@
+use rayon::prelude::*;
+
+use crate::{CVec, Encoding};
+use bitpacking::{BitPacker, BitPacker8x};
+
+impl CVec {
+    /// Builds a new vector from a slice by compressing its blocks in parallel. Since blocks are
+    /// independent of each other, this scales with the amount of threads for big slices.
+    pub fn from_par_slice(slice: &[u32]) -> Self {
+        let (blocks, bounds): (Vec<_>, Vec<_>) = slice
+            .par_chunks(BitPacker8x::BLOCK_LEN)
+            .map(|chunk| {
+                let mut block = Vec::new();
+                let header = Self::compress(chunk, &mut block, Encoding::Plain);
+                ((header, block), Self::min_max(chunk))
+            })
+            .unzip();
+
+        Self {
+            data: blocks,
+            bounds,
+            items: slice.len(),
+            ..Self::new()
+        }
+    }
+
+    /// Returns a parallel iterator over the values of the vector. Work gets split at block
+    /// boundaries and each block gets decompressed into its own buffer by the thread processing
+    /// it.
+    pub fn par_iter(&self) -> impl ParallelIterator<Item = u32> + '_ {
+        (0..Self::req_block_count(self.items))
+            .into_par_iter()
+            .flat_map_iter(move |block_nr| {
+                let mut block = vec![0u32; BitPacker8x::BLOCK_LEN];
+                self.decompress_block(block_nr, &mut block).unwrap();
+                block.truncate(self.block_len(block_nr));
+                block
+            })
+    }
+}
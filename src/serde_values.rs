@@ -0,0 +1,71 @@
+use std::fmt;
+
+use serde::{
+    de::{SeqAccess, Visitor},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+
+use crate::CVec;
+
+/// Serializes `vec` as a flat sequence of its values
+#[inline]
+pub fn serialize<S: Serializer>(vec: &CVec, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.collect_seq(vec.iter())
+}
+
+/// Deserializes a flat sequence of u32 values into a `CVec`
+#[inline]
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<CVec, D::Error> {
+    deserializer.deserialize_seq(ValuesVisitor)
+}
+
+/// Wrapper around a `CVec` which gets (de)serialized as a flat sequence of its values. Useful
+/// where `#[serde(with = "compressed_vec::serde_values")]` can't be used, eg. for top level values.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Values(pub CVec);
+
+impl Serialize for Values {
+    #[inline]
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize(&self.0, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Values {
+    #[inline]
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize(deserializer).map(Values)
+    }
+}
+
+impl From<CVec> for Values {
+    #[inline]
+    fn from(vec: CVec) -> Self {
+        Values(vec)
+    }
+}
+
+impl From<Values> for CVec {
+    #[inline]
+    fn from(values: Values) -> Self {
+        values.0
+    }
+}
+
+struct ValuesVisitor;
+
+impl<'de> Visitor<'de> for ValuesVisitor {
+    type Value = CVec;
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a sequence of u32 values")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut vec = CVec::new();
+        while let Some(val) = seq.next_element::<u32>()? {
+            vec.push(val);
+        }
+        Ok(vec)
+    }
+}
@@ -1,4 +1,14 @@
-use crate::CVec;
+use std::{
+    cmp::Ordering,
+    convert::TryFrom,
+    fmt::{self, Display},
+    hash::{Hash, Hasher},
+};
+
+use crate::{error::FromBlocksError, CVec, Encoding};
+use serde::{
+    de::Error as _, ser::SerializeStruct, Deserialize, Deserializer, Serialize, Serializer,
+};
 
 impl<T: AsRef<[u32]>> PartialEq<T> for CVec {
     #[inline]
@@ -14,6 +24,57 @@ impl PartialEq for CVec {
     }
 }
 
+impl Eq for CVec {}
+
+impl Hash for CVec {
+    /// Hashes the logical sequence of values (length prefix followed by each value), so vectors
+    /// with equal values have equal hashes regardless of how their blocks are compressed.
+    #[inline]
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_usize(self.len());
+        for i in self.iter() {
+            i.hash(state);
+        }
+    }
+}
+
+impl PartialOrd for CVec {
+    #[inline]
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for CVec {
+    /// Compares the values lexicographically like `Vec<u32>` does. Blocks get decompressed lazily
+    /// so the comparison stops decompressing at the first differing value.
+    #[inline]
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.iter().cmp(other.iter())
+    }
+}
+
+/// Amount of values `Display` prints before truncating the output with an ellipsis.
+const DISPLAY_LIMIT: usize = 32;
+
+impl Display for CVec {
+    /// Prints the values like a `Vec<u32>` would be debug printed. Vectors longer than
+    /// `DISPLAY_LIMIT` get truncated with a trailing `...`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[")?;
+        for (pos, i) in self.iter().take(DISPLAY_LIMIT).enumerate() {
+            if pos > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}", i)?;
+        }
+        if self.len() > DISPLAY_LIMIT {
+            write!(f, ", ...")?;
+        }
+        write!(f, "]")
+    }
+}
+
 impl PartialEq<CVec> for Vec<u32> {
     #[inline]
     fn eq(&self, other: &CVec) -> bool {
@@ -35,6 +96,13 @@ impl PartialEq<CVec> for &[u32] {
     }
 }
 
+impl<const N: usize> PartialEq<CVec> for [u32; N] {
+    #[inline]
+    fn eq(&self, other: &CVec) -> bool {
+        other.iter().eq(self.iter().copied())
+    }
+}
+
 impl<T: Into<u32> + Copy> From<&Vec<T>> for CVec {
     #[inline]
     fn from(vec: &Vec<T>) -> Self {
@@ -45,7 +113,23 @@ impl<T: Into<u32> + Copy> From<&Vec<T>> for CVec {
 impl<T: Into<u32>> From<Vec<T>> for CVec {
     #[inline]
     fn from(vec: Vec<T>) -> Self {
-        vec.into_iter().map(|i| i.into()).collect::<CVec>()
+        // Reuses the allocation of `vec` if T is u32
+        let vec = vec.into_iter().map(|i| i.into()).collect::<Vec<u32>>();
+        CVec::from_slice(&vec)
+    }
+}
+
+impl From<&[u32]> for CVec {
+    #[inline]
+    fn from(slice: &[u32]) -> Self {
+        CVec::from_slice(slice)
+    }
+}
+
+impl<const N: usize> From<&[u32; N]> for CVec {
+    #[inline]
+    fn from(array: &[u32; N]) -> Self {
+        CVec::from_slice(array)
     }
 }
 
@@ -62,3 +146,70 @@ impl<T: From<u32>> From<CVec> for Vec<T> {
         cvec.into_iter().map(|i| T::from(i)).collect::<Vec<T>>()
     }
 }
+
+impl Serialize for CVec {
+    /// Serializes the blocks which hold values. The staged last block gets compressed, so a vector
+    /// serializes the same regardless of whether its last block was flushed.
+    ///
+    /// `encoding` and `sorted` are serialized after the blocks since they were added. Formats
+    /// storing fields by name like JSON still load vectors serialized without them. Formats
+    /// storing fields by position like bincode can't load the old layout anymore, and older
+    /// versions of this crate can't load the new one.
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("CVec", 4)?;
+        state.serialize_field("data", &Blocks(self))?;
+        state.serialize_field("items", &self.items)?;
+        state.serialize_field("encoding", &self.encoding)?;
+        state.serialize_field("sorted", &self.sorted)?;
+        state.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for CVec {
+    /// Deserializes the blocks and validates them like `CVec::from_blocks`, so malformed input
+    /// results in an error instead of a vector which panics later on. The bounds of each block get
+    /// rebuilt from its values, and vectors in the sorted mode have to hold sorted values.
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        CVec::try_from(RawCVec::deserialize(deserializer)?).map_err(D::Error::custom)
+    }
+}
+
+/// The serialized fields of a `CVec`. `encoding` and `sorted` were added later on. Defaulting them
+/// only helps formats storing fields by name, formats like bincode fail on the missing fields.
+#[derive(Deserialize)]
+struct RawCVec {
+    data: Vec<(u8, Vec<u8>)>,
+    items: usize,
+    #[serde(default)]
+    encoding: Encoding,
+    #[serde(default)]
+    sorted: bool,
+}
+
+impl TryFrom<RawCVec> for CVec {
+    type Error = FromBlocksError;
+
+    fn try_from(raw: RawCVec) -> Result<Self, Self::Error> {
+        let mut blocks = raw.data;
+
+        // Older versions serialized preallocated blocks and whole block buffers
+        blocks.truncate(CVec::req_block_count(raw.items));
+        for (header, data) in blocks.iter_mut() {
+            data.truncate(CVec::compressed_len(*header));
+        }
+
+        let mut vec = CVec::from_blocks_checked(blocks, raw.items, raw.sorted)?;
+        vec.encoding = raw.encoding;
+        Ok(vec)
+    }
+}
+
+/// Serializes the used blocks of a `CVec` as sequence of `(header, data)` tuples
+struct Blocks<'a>(&'a CVec);
+
+impl<'a> Serialize for Blocks<'a> {
+    #[inline]
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq((0..self.0.block_count()).filter_map(|i| self.0.block_at(i)))
+    }
+}
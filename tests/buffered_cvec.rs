@@ -1,5 +1,5 @@
 use compressed_vec::{
-    buffered::{BufCVec, BufCVecRef},
+    buffered::{BufCVec, BufCVecMulti, BufCVecMut, BufCVecRef, Buffer, CVecWriter, MultiBuffer},
     CVec,
 };
 
@@ -83,3 +83,214 @@ fn buf_read_ref_from_cvec() {
 
     assert_eq!(buffer.get_buffered(10), Some(&10));
 }
+
+#[test]
+fn buffer_with_capacity() {
+    let cvec = (0..1000).collect::<CVec>();
+
+    let mut buffer = Buffer::new();
+    assert_eq!(buffer.capacity(), 0);
+    assert_eq!(buffer.read_buffered(&cvec, 10), Some(&10));
+    assert_eq!(buffer.capacity(), 256);
+
+    let mut buffer = Buffer::with_capacity();
+    assert_eq!(buffer.capacity(), 256);
+    assert_eq!(buffer.read_buffered(&cvec, 10), Some(&10));
+    assert_eq!(buffer.read_buffered(&cvec, 500), Some(&500));
+    assert_eq!(buffer.capacity(), 256);
+}
+
+#[test]
+fn buf_write_block() {
+    let mut v = (0..1000).collect::<CVec>();
+    let mut expected = v.clone();
+
+    {
+        let mut buffered = BufCVecMut::new(&mut v);
+        for i in 256..512 {
+            buffered.set_buffered(i, i as u32 * 3).unwrap();
+            assert_eq!(buffered.get_buffered(i), Some(&(i as u32 * 3)));
+        }
+        buffered.flush();
+
+        // Writes to the partially filled last block and block switches
+        buffered.set_buffered(999, 1).unwrap();
+        buffered.set_buffered(0, 2).unwrap();
+        buffered.set_buffered(998, 3).unwrap();
+        assert_eq!(buffered.set_buffered(1000, 0), None);
+        assert_eq!(buffered.get_buffered(0), Some(&2));
+    }
+
+    for i in 256..512 {
+        expected.set(i, i as u32 * 3).unwrap();
+    }
+    expected.set(999, 1).unwrap();
+    expected.set(0, 2).unwrap();
+    expected.set(998, 3).unwrap();
+    assert_eq!(v, expected);
+
+    // Writes into a block staged by `push`
+    let mut v = CVec::new();
+    v.extend(0..300);
+    v.push(300);
+    BufCVecMut::new(&mut v).set_buffered(300, 7).unwrap();
+    assert_eq!(v.last(), Some(7));
+    v.push(8);
+    assert_eq!(v.get(300), Some(7));
+    v.debug_assert_valid();
+}
+
+#[test]
+fn buffer_block_switches() {
+    let test_data = (0..2000).map(|i| i * 5).collect::<Vec<u32>>();
+    let v = CVec::from(test_data.as_slice());
+
+    for mut buffer in [Buffer::new(), Buffer::with_capacity()] {
+        for round in 0..3 {
+            for pos in [0, 1999, 256, 255, 1024, 0, 1999, 1500 + round] {
+                assert_eq!(buffer.read_buffered(&v, pos), Some(&test_data[pos]));
+            }
+        }
+        assert_eq!(buffer.read_buffered(&v, 2000), None);
+        assert_eq!(buffer.capacity(), 256);
+    }
+}
+
+#[test]
+fn multi_buffer() {
+    let test_data = (0..5000).map(|i| i * 3).collect::<Vec<u32>>();
+    let v = CVec::from(test_data.as_slice());
+
+    for n in [1, 2, 3, 8] {
+        let mut buffered = BufCVecMulti::new(&v, n);
+        for i in 0..1000 {
+            // Ping-pong between distant blocks
+            for pos in [i, 4999 - i, 2500 + i % 7] {
+                assert_eq!(buffered.get_buffered(pos), Some(&test_data[pos]));
+            }
+        }
+        assert_eq!(buffered.get_buffered(5000), None);
+    }
+
+    let mut buffer = MultiBuffer::with_blocks(2);
+    assert_eq!(buffer.max_blocks(), 2);
+    assert_eq!(buffer.read_buffered(&v, 10), Some(&30));
+    assert_eq!(buffer.read_buffered(&CVec::new(), 0), None);
+}
+
+#[test]
+#[should_panic]
+fn multi_buffer_no_blocks() {
+    MultiBuffer::with_blocks(0);
+}
+
+#[test]
+fn buf_invalidate() {
+    let mut buffered = BufCVec::new((0..1000).collect::<CVec>());
+    assert_eq!(buffered.get_buffered(10), Some(&10));
+
+    buffered.get_vec_mut().set(10, 99);
+    assert_eq!(buffered.get_buffered(10), Some(&99));
+
+    let mut vec = (0..1000).collect::<CVec>();
+    let mut buf = Buffer::new();
+    assert_eq!(buf.read_buffered(&vec, 300), Some(&300));
+
+    // Invalidating another block keeps the stale one
+    vec.set(300, 5);
+    buf.invalidate_block(0);
+    assert_eq!(buf.read_buffered(&vec, 300), Some(&300));
+
+    buf.invalidate_block(1);
+    assert_eq!(buf.read_buffered(&vec, 300), Some(&5));
+
+    vec.set(301, 6);
+    buf.clear();
+    assert_eq!(buf.read_buffered(&vec, 301), Some(&6));
+}
+
+#[test]
+fn buf_push_set_pop() {
+    let mut buffered = BufCVec::new(CVec::new());
+    let mut vec = Vec::new();
+
+    for i in 0..1000 {
+        buffered.push(i * 3);
+        vec.push(i * 3);
+        assert_eq!(buffered.get_buffered(i as usize), Some(&(i * 3)));
+        assert_eq!(
+            buffered.get_buffered(i as usize / 2),
+            Some(&vec[i as usize / 2])
+        );
+    }
+
+    assert_eq!(buffered.set(999, 1), Some(()));
+    assert_eq!(buffered.set(0, 2), Some(()));
+    assert_eq!(buffered.set(1000, 2), None);
+    vec[999] = 1;
+    vec[0] = 2;
+
+    assert_eq!(buffered.pop(), vec.pop());
+    assert_eq!(buffered.get_buffered(999), None);
+    buffered.push(7);
+    vec.push(7);
+
+    for (pos, i) in vec.iter().enumerate() {
+        assert_eq!(buffered.get_buffered(pos), Some(i));
+    }
+}
+
+#[test]
+fn buf_iter() {
+    let values = (0..1000).map(|i| i * 11 % 997).collect::<Vec<u32>>();
+    let mut buffered = BufCVec::new(CVec::from(values.as_slice()));
+
+    for _ in 0..2 {
+        let iter = buffered.iter();
+        assert_eq!(iter.len(), values.len());
+        assert!(iter.eq(values.iter().copied()));
+    }
+
+    let mut sum = 0u64;
+    for i in &mut buffered {
+        sum += i as u64;
+    }
+    assert_eq!(sum, values.iter().map(|i| *i as u64).sum::<u64>());
+
+    buffered.push(5);
+    assert_eq!(buffered.iter().last(), Some(5));
+}
+
+#[test]
+fn writer() {
+    let values = (0..5000).map(|i| i * 13 % 4099).collect::<Vec<u32>>();
+
+    let mut cvec = CVec::new();
+    let mut writer = CVecWriter::new(&mut cvec);
+    for batch in values.chunks(37) {
+        writer.write(batch);
+    }
+    writer.finish();
+    assert_eq!(cvec, CVec::from(values.as_slice()));
+    cvec.debug_assert_valid();
+
+    // Appending to a partially filled block, with batches bigger than a block
+    let mut cvec = CVec::from(&values[..100]);
+    {
+        let mut writer = CVecWriter::from(&mut cvec);
+        for batch in values[100..].chunks(600) {
+            writer.write(batch);
+        }
+        writer.write(&[]);
+    }
+    assert_eq!(cvec, values);
+
+    // Flushing in between
+    let mut cvec = CVec::new();
+    let mut writer = CVecWriter::new(&mut cvec);
+    writer.write(&values[..10]);
+    writer.flush();
+    writer.write(&values[10..300]);
+    drop(writer);
+    assert_eq!(cvec, &values[..300]);
+}
@@ -0,0 +1,167 @@
+use compressed_vec::{serde_values::Values, CVec, CVec64};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Document {
+    id: u32,
+    #[serde(with = "compressed_vec::serde_values")]
+    terms: CVec,
+}
+
+#[test]
+fn serialize_blocks() {
+    let cvec = (0..1000).map(|i| i * 3).collect::<CVec>();
+
+    let json = serde_json::to_string(&cvec).unwrap();
+    let deserialized: CVec = serde_json::from_str(&json).unwrap();
+    assert_eq!(deserialized, cvec);
+    deserialized.debug_assert_valid();
+
+    let mut pushed = CVec::new();
+    for i in 0..300 {
+        pushed.push(i);
+    }
+    let json = serde_json::to_string(&pushed).unwrap();
+    let mut deserialized: CVec = serde_json::from_str(&json).unwrap();
+    assert_eq!(deserialized, pushed);
+    deserialized.push(300);
+    assert_eq!(deserialized, (0..301).collect::<Vec<_>>());
+}
+
+#[test]
+fn serialize_values() {
+    let cvec = (0..5).collect::<CVec>();
+
+    let json = serde_json::to_string(&Values(cvec.clone())).unwrap();
+    assert_eq!(json, "[0,1,2,3,4]");
+    let deserialized: Values = serde_json::from_str(&json).unwrap();
+    assert_eq!(deserialized.0, cvec);
+
+    let doc = Document {
+        id: 1,
+        terms: (0..1000).collect(),
+    };
+    let json = serde_json::to_string(&doc).unwrap();
+    assert!(json.starts_with(r#"{"id":1,"terms":[0,1,2,"#));
+    let deserialized: Document = serde_json::from_str(&json).unwrap();
+    assert_eq!(deserialized, doc);
+
+    let empty: Values = serde_json::from_str("[]").unwrap();
+    assert!(empty.0.is_empty());
+    assert!(serde_json::from_str::<Values>("[1,-1]").is_err());
+}
+
+#[test]
+fn to_bytes_smaller_than_bincode() {
+    for cvec in [
+        (0..10_000).collect::<CVec>(),
+        (0..10_000).map(|i| i % 3).collect::<CVec>(),
+        (0..1000).map(|i| i * 4_000_000).collect::<CVec>(),
+    ] {
+        let bytes = cvec.to_bytes();
+        let bincode = bincode::serialize(&cvec).unwrap();
+        assert!(bytes.len() < bincode.len());
+        assert_eq!(CVec::from_bytes(&bytes).unwrap(), cvec);
+    }
+}
+
+#[test]
+fn serialize_staged_block() {
+    let mut staged = CVec::new();
+    for i in 0..300 {
+        staged.push(i);
+    }
+    let mut flushed = staged.clone();
+    flushed.shrink_to_fit();
+
+    let staged_json = serde_json::to_string(&staged).unwrap();
+    assert_eq!(staged_json, serde_json::to_string(&flushed).unwrap());
+    assert!(!staged_json.contains("tail"));
+    assert_eq!(
+        bincode::serialize(&staged).unwrap(),
+        bincode::serialize(&flushed).unwrap()
+    );
+
+    let mut deserialized: CVec = serde_json::from_str(&staged_json).unwrap();
+    deserialized.debug_assert_valid();
+    deserialized.push(300);
+    assert_eq!(deserialized, (0..301).collect::<Vec<_>>());
+}
+
+#[test]
+fn deserialize_validates_blocks() {
+    let cvec = (0..1000).map(|i| i * 3).collect::<CVec>();
+
+    // Vectors serialized before `encoding` and `sorted` existed
+    let mut value = serde_json::to_value(&cvec).unwrap();
+    let fields = value.as_object_mut().unwrap();
+    fields.remove("encoding");
+    fields.remove("sorted");
+    let deserialized: CVec = serde_json::from_value(value.clone()).unwrap();
+    deserialized.debug_assert_valid();
+    assert_eq!(deserialized, cvec);
+
+    let mut crafted = value.clone();
+    crafted["items"] = 5000.into();
+    assert!(serde_json::from_value::<CVec>(crafted).is_err());
+
+    let mut crafted = value.clone();
+    crafted["data"][0][0] = 63.into();
+    assert!(serde_json::from_value::<CVec>(crafted).is_err());
+
+    // The sorted mode requires sorted values
+    let mut sorted = value;
+    sorted["sorted"] = true.into();
+    let deserialized: CVec = serde_json::from_value(sorted).unwrap();
+    assert!(deserialized.is_sorted());
+
+    let mut crafted = serde_json::to_value((0..1000).rev().collect::<CVec>()).unwrap();
+    crafted["sorted"] = true.into();
+    assert!(serde_json::from_value::<CVec>(crafted).is_err());
+}
+
+/// The layout vectors were serialized with before `encoding` and `sorted` were added
+#[derive(Serialize)]
+struct OldCVec {
+    data: Vec<(u8, Vec<u8>)>,
+    items: usize,
+}
+
+#[test]
+fn deserialize_old_format() {
+    let cvec = (0..1000).map(|i| i * 3).collect::<CVec>();
+    let old = OldCVec {
+        data: (0..cvec.block_count())
+            .map(|i| {
+                let (header, data) = cvec.block_at(i).unwrap();
+                (header, data.into_owned())
+            })
+            .collect(),
+        items: cvec.len(),
+    };
+
+    // Formats storing fields by name fill in the missing fields
+    let json = serde_json::to_string(&old).unwrap();
+    assert_eq!(serde_json::from_str::<CVec>(&json).unwrap(), cvec);
+
+    // bincode reads fields by position, so the old layout can't be loaded anymore
+    let bytes = bincode::serialize(&old).unwrap();
+    assert!(bincode::deserialize::<CVec>(&bytes).is_err());
+
+    let bytes = bincode::serialize(&cvec).unwrap();
+    let deserialized: CVec = bincode::deserialize(&bytes).unwrap();
+    assert_eq!(deserialized, cvec);
+    deserialized.debug_assert_valid();
+}
+
+#[test]
+fn deserialize_cvec64() {
+    let cvec = (0..1000u64).map(|i| i << 30).collect::<CVec64>();
+    let json = serde_json::to_string(&cvec).unwrap();
+    assert_eq!(serde_json::from_str::<CVec64>(&json).unwrap(), cvec);
+
+    // Lanes holding different amounts of values
+    let mut value = serde_json::to_value(&cvec).unwrap();
+    value["high"] = serde_json::to_value((0..999).collect::<CVec>()).unwrap();
+    assert!(serde_json::from_value::<CVec64>(value).is_err());
+}
@@ -1,4 +1,9 @@
-use compressed_vec::CVec;
+use bitpacking::{BitPacker, BitPacker8x};
+use compressed_vec::{
+    buffered::BufCVecMut,
+    error::{CompressError, FromBlocksError, FromBytesError, ValueOverflowError},
+    CVec, CVec64, CVecBuilder, Encoding,
+};
 
 #[test]
 fn push_with_capacity() {
@@ -112,6 +117,34 @@ fn pop_simple() {
     }
 }
 
+#[test]
+fn pop_single_block_to_empty() {
+    for data in [(0..256).collect::<CVec>(), {
+        let mut v = CVec::new();
+        (0..256).for_each(|i| v.push(i));
+        v
+    }] {
+        let mut v = data;
+        assert_eq!(v.capacity(), 256);
+
+        for i in (0..256).rev() {
+            assert_eq!(v.pop(), Some(i));
+        }
+        assert_eq!(v.pop(), None);
+        assert!(v.is_empty());
+        v.debug_assert_valid();
+
+        // The emptied block stays allocated for reuse
+        assert_eq!(v.capacity(), 256);
+        v.push(5);
+        assert_eq!(v, [5]);
+        v.pop();
+        v.shrink_to_fit();
+        assert_eq!(v.capacity(), 0);
+        assert_eq!(v.byte_len(), CVec::new().byte_len());
+    }
+}
+
 #[test]
 fn pop() {
     let mut v = CVec::new();
@@ -183,6 +216,28 @@ fn iter() {
     assert_eq!(iter.next(), None);
 }
 
+#[test]
+fn iter_size_hint() {
+    let cvec = (0..300).collect::<CVec>();
+    let mut iter = cvec.iter();
+    assert_eq!(iter.size_hint(), (300, Some(300)));
+
+    iter.next();
+    assert_eq!(iter.size_hint(), (299, Some(299)));
+
+    iter.nth(256);
+    assert_eq!(iter.size_hint(), (42, Some(42)));
+
+    iter.next_back();
+    assert_eq!(iter.size_hint(), (41, Some(41)));
+
+    let mut iter = cvec.into_iter().skip(299);
+    assert_eq!(iter.size_hint(), (1, Some(1)));
+    iter.next();
+    assert_eq!(iter.size_hint(), (0, Some(0)));
+    assert_eq!(iter.next(), None);
+}
+
 #[test]
 fn from_iter() {
     let inp = (0..10).into_iter();
@@ -250,3 +305,2292 @@ fn extend_test(a_len: usize, b_len: usize) {
         assert_eq!(expected, real);
     }
 }
+
+#[test]
+fn shrink_to_fit() {
+    let mut v = CVec::with_capacity(10000);
+    for i in 0..1000 {
+        v.push(i);
+    }
+    assert_eq!(v.capacity(), 10240);
+
+    let before = v.capacity_in_bytes();
+    let reclaimed = v.shrink_to_fit();
+    assert!(reclaimed > 0);
+    assert_eq!(reclaimed, before - v.capacity_in_bytes());
+    assert_eq!(v.capacity(), 1024);
+    assert_eq!(v, (0..1000).collect::<Vec<_>>());
+
+    // Nothing left to reclaim
+    assert_eq!(v.shrink_to_fit(), 0);
+}
+
+#[test]
+fn shrink_to_fit_after_removal() {
+    let mut v = CVec::with_capacity(100_000);
+    v.extend(0..50_000);
+    for _ in 0..40_000 {
+        v.pop();
+    }
+
+    let capacity = v.capacity();
+    let byte_len = v.byte_len();
+    v.shrink_to_fit();
+    assert!(v.capacity() < capacity);
+    assert_eq!(v.capacity(), 10_240);
+    assert!(v.byte_len() < byte_len);
+    assert_eq!(v, (0..10_000).collect::<Vec<_>>());
+    v.debug_assert_valid();
+
+    // `truncate` already drops the blocks
+    v.truncate(1000);
+    assert_eq!(v.capacity(), 1024);
+}
+
+#[test]
+fn from_iter_capped() {
+    let cvec = CVec::from_iter_capped(0.., 1000);
+    assert_eq!(cvec.len(), 1000);
+    assert_eq!(cvec, (0..1000).collect::<Vec<_>>());
+
+    let cvec = CVec::from_iter_capped(0..10, 1000);
+    assert_eq!(cvec, (0..10).collect::<Vec<_>>());
+}
+
+#[test]
+fn iter_refs() {
+    let cvec = (0..1000).collect::<CVec>();
+
+    let mut iter = cvec.iter_refs();
+    let mut pos = 0;
+    while let Some(val) = iter.next_ref() {
+        assert_eq!(*val, pos);
+        pos += 1;
+    }
+    assert_eq!(pos, 1000);
+    assert_eq!(iter.next_ref(), None);
+}
+
+#[test]
+fn from_vec() {
+    for len in [0, 1, 255, 256, 257, 1000, 1024] {
+        let vec = (0..len).collect::<Vec<u32>>();
+        let cvec = CVec::from(vec.clone());
+        assert_eq!(cvec.len(), vec.len());
+        assert_eq!(cvec, vec);
+        assert_eq!(cvec, vec.iter().copied().collect::<CVec>());
+    }
+
+    let cvec = CVec::from(vec![1u8, 2, 3]);
+    assert_eq!(cvec, vec![1, 2, 3]);
+}
+
+#[test]
+fn from_raw_block() {
+    for num_bits in [0u8, 1, 7, 8, 13, 31, 32] {
+        let max = if num_bits == 32 {
+            u32::MAX
+        } else {
+            (1u32 << num_bits) - 1
+        };
+        let mut values = [0u32; 256];
+        for (pos, val) in values.iter_mut().enumerate() {
+            *val = (pos as u32).wrapping_mul(2654435761) & max;
+        }
+
+        let (bits, block) = CVec::from_raw_block(num_bits, &values);
+        assert_eq!(bits, num_bits);
+        assert_eq!(block.len(), 32 * num_bits as usize);
+
+        let mut decompressed = [0u32; 256];
+        BitPacker8x::new().decompress(&block, &mut decompressed, bits);
+        assert_eq!(decompressed, values);
+    }
+}
+
+#[test]
+#[should_panic]
+fn from_raw_block_too_small() {
+    CVec::from_raw_block(4, &[16u32; 256]);
+}
+
+#[test]
+fn pop_keeps_capacity() {
+    let mut v = CVec::with_capacity(1000);
+    for i in 0..300 {
+        v.push(i);
+    }
+
+    for _ in 0..100 {
+        v.pop();
+    }
+    assert_eq!(v.len(), 200);
+    assert_eq!(v.capacity(), 1024);
+    assert_eq!(v, (0..200).collect::<Vec<_>>());
+
+    // Refill the emptied block
+    v.extend(200..1000);
+    assert_eq!(v.capacity(), 1024);
+    assert_eq!(v, (0..1000).collect::<Vec<_>>());
+}
+
+#[test]
+fn iter_double_ended() {
+    let cvec = (0..1000).collect::<CVec>();
+
+    let mut iter = cvec.iter();
+    let mut front = 0;
+    let mut back = 1000;
+    for step in 0..1000 {
+        assert_eq!(iter.len(), back - front);
+        if step % 3 == 0 {
+            back -= 1;
+            assert_eq!(iter.next_back(), Some(back as u32));
+        } else {
+            assert_eq!(iter.next(), Some(front as u32));
+            front += 1;
+        }
+    }
+    assert_eq!(iter.len(), 0);
+    assert_eq!(iter.next(), None);
+    assert_eq!(iter.next_back(), None);
+
+    let rev = cvec.clone().into_iter().rev().collect::<Vec<_>>();
+    assert_eq!(rev, (0..1000).rev().collect::<Vec<_>>());
+
+    let mut iter = cvec.into_iter();
+    iter.next_back();
+    iter.next();
+    assert_eq!(iter.len(), 998);
+}
+
+#[test]
+fn signed_blocks() {
+    let large_positive = (0..256u32).map(|i| 1_000_000 + i);
+    let small_negative = (0..256i32).map(|i| -(i % 5) as u32);
+    let mixed = (0..100i32).map(|i| if i % 2 == 0 { i as u32 } else { -i as u32 });
+    let data = large_positive
+        .chain(small_negative)
+        .chain(mixed)
+        .collect::<Vec<_>>();
+
+    let mut cvec = data.iter().copied().collect::<CVec>();
+    assert_eq!(cvec.block_signed_flags(), vec![false, true, true]);
+    assert_eq!(cvec, data);
+    for (pos, val) in data.iter().enumerate() {
+        assert_eq!(cvec.get(pos), Some(*val));
+    }
+
+    // Signed blocks store their values in few bits
+    let unsigned = (0..512).map(|_| 1_000_000).collect::<CVec>();
+    assert!(cvec.byte_len() < unsigned.byte_len());
+
+    cvec.set(300, u32::MAX);
+    cvec.push(-20i32 as u32);
+    assert_eq!(cvec.get(300), Some(u32::MAX));
+    assert_eq!(cvec.last(), Some(-20i32 as u32));
+    assert_eq!(cvec.block_signed_flags(), vec![false, true, true]);
+
+    cvec.set(300, 3_000_000_000);
+    assert_eq!(cvec.block_signed_flags(), vec![false, false, true]);
+    assert_eq!(cvec.get(300), Some(3_000_000_000));
+}
+
+#[test]
+fn clone_range() {
+    let reference = (0..1000).map(|i| i * 7).collect::<Vec<u32>>();
+    let cvec = CVec::from(reference.clone());
+
+    let cloned = cvec.clone_range(300..700);
+    assert_eq!(cloned, CVec::from(reference[300..700].to_vec()));
+    assert_eq!(cloned.len(), 400);
+
+    for (start, end) in [
+        (0, 1000),
+        (256, 700),
+        (256, 768),
+        (512, 1000),
+        (0, 0),
+        (999, 1000),
+    ] {
+        let cloned = cvec.clone_range(start..end);
+        assert_eq!(cloned, &reference[start..end]);
+        assert_eq!(cloned.len(), end - start);
+    }
+
+    assert_eq!(cvec.clone_range(..), reference);
+    assert_eq!(cvec.clone_range(..=255), &reference[..=255]);
+    assert_eq!(cvec.clone_range(900..), &reference[900..]);
+}
+
+#[test]
+#[should_panic]
+fn clone_range_out_of_bounds() {
+    let cvec = (0..10).collect::<CVec>();
+    cvec.clone_range(5..11);
+}
+
+#[test]
+fn extend_repeat() {
+    let mut cvec = (0..156).collect::<CVec>();
+    cvec.extend_repeat(42, 100_000);
+
+    assert_eq!(cvec.len(), 100_156);
+    assert_eq!(cvec.get(155), Some(155));
+    for pos in [156, 255, 256, 1000, 50_000, 100_155] {
+        assert_eq!(cvec.get(pos), Some(42));
+    }
+    assert_eq!(cvec.get(100_156), None);
+
+    // Every full block of 42s only needs 6 bits per value
+    assert!(cvec.byte_len() < 100_000);
+
+    let mut expected = (0..156).collect::<Vec<_>>();
+    expected.resize(100_156, 42);
+    assert_eq!(cvec, expected);
+
+    let mut cvec = CVec::new();
+    cvec.extend_repeat(7, 10);
+    cvec.extend_repeat(8, 0);
+    assert_eq!(cvec, vec![7; 10]);
+}
+
+#[test]
+fn insert() {
+    for len in [0, 1, 100, 255, 256, 257, 511, 512, 1000] {
+        for pos in [0, 1, 100, 255, 256, 257, 300, 511, 512, 999, 1000] {
+            if pos > len {
+                continue;
+            }
+
+            let mut reference = (0..len as u32).collect::<Vec<_>>();
+            let mut cvec = CVec::from(reference.clone());
+
+            reference.insert(pos, 1_000_000);
+            cvec.insert(pos, 1_000_000);
+
+            assert_eq!(cvec.len(), reference.len());
+            assert_eq!(cvec, reference);
+        }
+    }
+
+    let mut cvec = CVec::new();
+    let mut reference = Vec::new();
+    for i in 0..600 {
+        let pos = (i * 7) % (reference.len() + 1);
+        cvec.insert(pos, i as u32);
+        reference.insert(pos, i as u32);
+    }
+    assert_eq!(cvec, reference);
+}
+
+#[test]
+#[should_panic(expected = "insertion index (is 11) should be <= len (is 10)")]
+fn insert_out_of_bounds() {
+    let mut cvec = (0..10).collect::<CVec>();
+    cvec.insert(11, 0);
+}
+
+#[test]
+fn is_all_zero() {
+    assert!(CVec::new().is_all_zero());
+
+    let mut cvec = CVec::from(vec![0u32; 1000]);
+    assert!(cvec.is_all_zero());
+
+    cvec.set(700, 1);
+    assert!(!cvec.is_all_zero());
+
+    cvec.set(700, 0);
+    cvec.push(5);
+    assert!(!cvec.is_all_zero());
+
+    // The popped value must not be taken into account
+    cvec.pop();
+    assert!(cvec.is_all_zero());
+
+    let mixed = (0..1000).collect::<CVec>();
+    assert!(!mixed.is_all_zero());
+}
+
+#[test]
+fn remove() {
+    for len in [1, 100, 255, 256, 257, 511, 512, 1000] {
+        for pos in [0, 1, 100, 255, 256, 257, 300, 511, 999] {
+            if pos >= len {
+                continue;
+            }
+
+            let mut reference = (0..len as u32).collect::<Vec<_>>();
+            let mut cvec = CVec::from(reference.clone());
+
+            assert_eq!(cvec.remove(pos), reference.remove(pos));
+            assert_eq!(cvec.len(), reference.len());
+            assert_eq!(cvec, reference);
+        }
+    }
+
+    let mut reference = (0..600).collect::<Vec<u32>>();
+    let mut cvec = CVec::from(reference.clone());
+    for i in 0..600 {
+        let pos = (i * 31) % reference.len();
+        assert_eq!(cvec.remove(pos), reference.remove(pos));
+    }
+    assert!(cvec.is_empty());
+
+    cvec.push(3);
+    assert_eq!(cvec, vec![3]);
+}
+
+#[test]
+#[should_panic(expected = "removal index (is 10) should be < len (is 10)")]
+fn remove_out_of_bounds() {
+    let mut cvec = (0..10).collect::<CVec>();
+    cvec.remove(10);
+}
+
+#[test]
+fn from_slice() {
+    let cvec = CVec::from(&[1u32, 2, 3][..]);
+    assert_eq!(cvec, vec![1, 2, 3]);
+
+    let reference = (0..1000).collect::<Vec<u32>>();
+    let cvec = CVec::from(&reference[10..900]);
+    assert_eq!(cvec.len(), 890);
+    assert_eq!(cvec, &reference[10..900]);
+
+    // Array references don't need to be sliced
+    assert_eq!(CVec::from(&[1, 2, 3]), vec![1, 2, 3]);
+    assert_eq!(CVec::from(&[7; 300]), vec![7; 300]);
+    assert!(CVec::from(&[]).is_empty());
+}
+
+#[test]
+fn iter_min_max() {
+    let reference = (0..2000u32)
+        .map(|i| i.wrapping_mul(2654435761) % 100_000)
+        .collect::<Vec<_>>();
+    let cvec = CVec::from(reference.clone());
+
+    assert_eq!(cvec.iter().max(), reference.iter().copied().max());
+    assert_eq!(cvec.iter().min(), reference.iter().copied().min());
+    assert_eq!(
+        cvec.clone().into_iter().max(),
+        reference.iter().copied().max()
+    );
+    assert_eq!(
+        cvec.clone().into_iter().min(),
+        reference.iter().copied().min()
+    );
+
+    // Only the remaining values are taken into account
+    for skip in [1, 255, 256, 700] {
+        let mut iter = cvec.iter();
+        for _ in 0..skip {
+            iter.next();
+            iter.next_back();
+        }
+        let rest = &reference[skip..2000 - skip];
+        assert_eq!(iter.max(), rest.iter().copied().max());
+
+        let mut iter = cvec.clone().into_iter();
+        for _ in 0..skip {
+            iter.next();
+            iter.next_back();
+        }
+        assert_eq!(iter.min(), rest.iter().copied().min());
+    }
+
+    assert_eq!(CVec::new().iter().max(), None);
+    assert_eq!(CVec::new().into_iter().min(), None);
+}
+
+#[test]
+fn clear() {
+    let mut cvec = (0..1000).collect::<CVec>();
+    cvec.clear();
+    assert_eq!(cvec.len(), 0);
+    assert!(cvec.is_empty());
+    assert_eq!(cvec.capacity(), 0);
+    assert_eq!(cvec.get(0), None);
+
+    cvec.extend(5..600);
+    assert_eq!(cvec, (5..600).collect::<Vec<_>>());
+
+    let mut cvec = (0..1000).collect::<CVec>();
+    cvec.clear_keep_capacity();
+    assert_eq!(cvec.len(), 0);
+    assert!(cvec.is_empty());
+    assert_eq!(cvec.capacity(), 1024);
+    assert_eq!(cvec.get(0), None);
+
+    for i in 0..300 {
+        cvec.push(i * 2);
+    }
+    assert_eq!(cvec.capacity(), 1024);
+    for i in 0..300 {
+        assert_eq!(cvec.get(i), Some(i as u32 * 2));
+    }
+}
+
+#[test]
+fn checked_compress() {
+    let mut out = Vec::new();
+
+    let data = vec![1u32; 300];
+    assert_eq!(
+        CVec::checked_compress(&data, &mut out),
+        Err(CompressError::TooManyValues(300))
+    );
+
+    let data = vec![1u32; 100];
+    assert_eq!(CVec::checked_compress(&data, &mut out), Ok(1));
+    assert_eq!(out.len(), 32);
+
+    let data = vec![u32::MAX >> 1; 256];
+    assert_eq!(CVec::checked_compress(&data, &mut out), Ok(31));
+}
+
+#[test]
+fn truncate() {
+    let reference = (0..1000).map(|i| i * 1000).collect::<Vec<u32>>();
+
+    for len in [1000, 999, 700, 512, 300, 256, 255, 1, 0] {
+        let mut cvec = CVec::from(reference.clone());
+        let byte_len = cvec.byte_len();
+
+        cvec.truncate(len);
+        assert_eq!(cvec.len(), len);
+        assert_eq!(cvec, &reference[..len]);
+        if len <= 768 {
+            // At least one block got dropped
+            assert!(cvec.byte_len() < byte_len);
+        }
+
+        cvec.push(1);
+        assert_eq!(cvec.get(len), Some(1));
+    }
+
+    // Truncating to a bigger length does nothing
+    let mut cvec = CVec::from(reference.clone());
+    cvec.truncate(2000);
+    assert_eq!(cvec, reference);
+
+    // The dropped values don't take up space anymore
+    let mut cvec = CVec::from(vec![1u32; 200]);
+    cvec.push(u32::MAX >> 1);
+    let byte_len = cvec.byte_len();
+    cvec.truncate(200);
+    assert_eq!(cvec.byte_len(), byte_len - 30 * 32);
+}
+
+#[test]
+fn rolling_max_min() {
+    let cvec = CVec::from(vec![1u32, 3, 2, 5, 4]);
+    assert_eq!(cvec.rolling_max(2), vec![3, 3, 5, 5]);
+    assert_eq!(cvec.rolling_min(2), vec![1, 2, 2, 4]);
+    assert_eq!(cvec.rolling_max(1), cvec);
+    assert_eq!(cvec.rolling_max(5), vec![5]);
+    assert!(cvec.rolling_max(6).is_empty());
+
+    let reference = (0..2000u32)
+        .map(|i| i.wrapping_mul(2654435761) % 1000)
+        .collect::<Vec<_>>();
+    let cvec = CVec::from(reference.clone());
+    for window in [3, 100, 300] {
+        let max = reference
+            .windows(window)
+            .map(|i| *i.iter().max().unwrap())
+            .collect::<Vec<_>>();
+        let min = reference
+            .windows(window)
+            .map(|i| *i.iter().min().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(cvec.rolling_max(window), max);
+        assert_eq!(cvec.rolling_min(window), min);
+    }
+}
+
+#[test]
+fn split_first_last() {
+    let reference = (0..1000).map(|i| i * 3).collect::<Vec<u32>>();
+    let cvec = CVec::from(reference.clone());
+
+    let (first, rest) = cvec.split_first().unwrap();
+    let (ref_first, ref_rest) = reference.split_first().unwrap();
+    assert_eq!(first, *ref_first);
+    assert_eq!(rest, ref_rest);
+
+    let (rest, last) = cvec.split_last().unwrap();
+    let (ref_last, ref_rest) = reference.split_last().unwrap();
+    assert_eq!(last, *ref_last);
+    assert_eq!(rest, ref_rest);
+
+    let single = CVec::from(vec![7u32]);
+    assert_eq!(single.split_first(), Some((7, CVec::new())));
+    assert_eq!(single.split_last(), Some((CVec::new(), 7)));
+
+    assert_eq!(CVec::new().split_first(), None);
+    assert_eq!(CVec::new().split_last(), None);
+}
+
+#[test]
+fn extend_large_then_small() {
+    // Alternate between blocks requiring many and few bits
+    let reference = (0..2000u32)
+        .map(|i| {
+            if (i / 256) % 2 == 0 {
+                u32::MAX >> 1
+            } else {
+                i % 4
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let mut cvec = CVec::new();
+    cvec.extend(reference.iter().copied());
+    assert_eq!(cvec, reference);
+
+    let mut cvec = (0..10).collect::<CVec>();
+    cvec.extend(reference.iter().copied());
+    assert_eq!(cvec.len(), 2010);
+    for (pos, val) in reference.iter().enumerate() {
+        assert_eq!(cvec.get(pos + 10), Some(*val));
+    }
+}
+
+#[test]
+fn dedup_by() {
+    let mut cvec = CVec::from(vec![1u32, 2, 11, 12, 21]);
+    cvec.dedup_by_key(|i| i / 10);
+    assert_eq!(cvec, vec![1, 11, 21]);
+
+    let reference = (0..2000u32).map(|i| i / 7).collect::<Vec<_>>();
+
+    let mut cvec = CVec::from(reference.clone());
+    let mut vec = reference.clone();
+    cvec.dedup_by(|a, b| a - b < 3);
+    vec.dedup_by(|a, b| *a - *b < 3);
+    assert_eq!(cvec, vec);
+
+    let mut cvec = CVec::from(reference.clone());
+    let mut vec = reference;
+    cvec.dedup_by_key(|i| i / 5);
+    vec.dedup_by_key(|i| *i / 5);
+    assert_eq!(cvec, vec);
+
+    let mut empty = CVec::new();
+    empty.dedup_by_key(|i| i);
+    assert!(empty.is_empty());
+}
+
+#[test]
+fn extend_partial_chunks() {
+    for len in [1, 37, 100, 255, 257, 300, 555, 1000] {
+        let reference = (0..len as u32).map(|i| 8 + i % 8).collect::<Vec<_>>();
+
+        let mut cvec = CVec::new();
+        cvec.extend(reference.iter().copied());
+        assert_eq!(cvec.len(), len);
+        assert_eq!(cvec, reference);
+
+        // Each block only needs 4 bits per value, even the padded partial one
+        let blocks = len.div_ceil(256);
+        let expected = std::mem::size_of::<usize>() * 2 + blocks * (1 + 4 * 32);
+        assert_eq!(cvec.byte_len(), expected);
+    }
+}
+
+#[test]
+fn extend_from_slice() {
+    for a_len in [0, 1, 100, 256, 300, 512] {
+        for b_len in [0, 1, 155, 156, 256, 700] {
+            let mut vec = (0..a_len).collect::<Vec<u32>>();
+            let mut cvec = CVec::from(vec.clone());
+            let slice = (0..b_len).map(|i| i * 13).collect::<Vec<u32>>();
+
+            vec.extend_from_slice(&slice);
+            cvec.extend_from_slice(&slice);
+            assert_eq!(cvec.len(), vec.len());
+            assert_eq!(cvec, vec);
+        }
+    }
+
+    // Fills up preallocated blocks
+    let mut cvec = CVec::with_capacity(1000);
+    cvec.extend_from_slice(&(0..900).collect::<Vec<_>>());
+    assert_eq!(cvec.capacity(), 1024);
+    assert_eq!(cvec, (0..900).collect::<Vec<_>>());
+}
+
+#[test]
+fn extend_panic_safety() {
+    let mut cvec = (0..156).collect::<CVec>();
+
+    let res = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        cvec.extend((0..1000).inspect(|i| {
+            if *i == 400 {
+                panic!("iterator failed");
+            }
+        }));
+    }));
+    assert!(res.is_err());
+    cvec.debug_assert_valid();
+
+    // All values pushed before the panic are readable
+    assert!(cvec.len() >= 156);
+    let expected = (0..156).chain(0..400).take(cvec.len()).collect::<Vec<_>>();
+    assert_eq!(cvec, expected);
+
+    cvec.push(5);
+    cvec.debug_assert_valid();
+    assert_eq!(cvec.last(), Some(5));
+}
+
+#[test]
+fn to_strings_radix() {
+    let cvec = CVec::from(vec![255u32, 16]);
+    assert_eq!(cvec.to_strings_radix(16), vec!["ff", "10"]);
+    assert_eq!(cvec.to_strings_radix(2), vec!["11111111", "10000"]);
+
+    let cvec = CVec::from(vec![0u32, 35, u32::MAX]);
+    assert_eq!(cvec.to_strings_radix(36), vec!["0", "z", "1z141z3"]);
+    assert_eq!(
+        cvec.to_strings_radix(10),
+        vec!["0".to_string(), "35".to_string(), u32::MAX.to_string()]
+    );
+}
+
+#[test]
+fn append() {
+    for a_len in [0, 1, 255, 256, 300, 512] {
+        for b_len in [0, 1, 256, 700] {
+            let mut vec_a = (0..a_len).collect::<Vec<u32>>();
+            let mut vec_b = (0..b_len).map(|i| i * 5).collect::<Vec<u32>>();
+            let mut cvec_a = CVec::from(vec_a.clone());
+            let mut cvec_b = CVec::from(vec_b.clone());
+
+            vec_a.append(&mut vec_b);
+            cvec_a.append(&mut cvec_b);
+
+            assert_eq!(cvec_a.len(), vec_a.len());
+            assert_eq!(cvec_a, vec_a);
+            assert!(cvec_b.is_empty());
+            assert_eq!(cvec_b, vec_b);
+
+            cvec_a.push(1);
+            cvec_b.push(2);
+            assert_eq!(cvec_a.last(), Some(1));
+            assert_eq!(cvec_b, vec![2]);
+        }
+    }
+
+    // Preallocated blocks don't end up between the appended values
+    let mut cvec = CVec::with_capacity(1000);
+    cvec.extend(0..256);
+    cvec.append(&mut (256..600).collect::<CVec>());
+    assert_eq!(cvec, (0..600).collect::<Vec<_>>());
+}
+
+#[test]
+fn set_many() {
+    let reference = (0..3000).collect::<CVec>();
+
+    let updates = (0..500u32)
+        .map(|i| ((i as usize * 7919) % 3000, i * 3))
+        .collect::<Vec<_>>();
+
+    let mut expected = reference.clone();
+    for (pos, val) in updates.iter() {
+        expected.set(*pos, *val).unwrap();
+    }
+
+    let mut cvec = reference.clone();
+    assert_eq!(cvec.set_many(&updates), Some(()));
+    assert_eq!(cvec, expected);
+
+    // Later updates of the same position win
+    cvec.set_many(&[(10, 1), (10, 2)]).unwrap();
+    assert_eq!(cvec.get(10), Some(2));
+
+    // Nothing gets changed if a position is out of bounds
+    let mut cvec = reference.clone();
+    assert_eq!(cvec.set_many(&[(0, 100), (3000, 1)]), None);
+    assert_eq!(cvec, reference);
+}
+
+#[test]
+fn block_byte_ranges() {
+    let cvec = (0..1000).map(|i| i * i).collect::<CVec>();
+    let bytes = cvec.to_bytes();
+    let ranges = cvec.block_byte_ranges();
+
+    assert_eq!(ranges.len(), 4);
+    assert_eq!(ranges[0].start, 8);
+    for pair in ranges.windows(2) {
+        assert_eq!(pair[0].end, pair[1].start);
+    }
+    assert_eq!(ranges.last().unwrap().end, bytes.len());
+
+    // Each range starts with the blocks header followed by its data
+    for range in ranges {
+        let num_bits = bytes[range.start] as usize;
+        assert_eq!(range.len(), 1 + num_bits * 32);
+    }
+
+    // Preallocated blocks are not part of the serialized data
+    let mut cvec = CVec::with_capacity(2000);
+    cvec.extend(0..300);
+    assert_eq!(cvec.block_byte_ranges().len(), 2);
+    assert_eq!(CVec::new().block_byte_ranges(), vec![]);
+    assert_eq!(CVec::new().to_bytes().len(), 8);
+}
+
+#[test]
+fn get_back() {
+    let reference = (0..1000).map(|i| i * 2).collect::<Vec<u32>>();
+    let cvec = CVec::from(reference.clone());
+
+    assert_eq!(cvec.get_back(0), cvec.last());
+    assert_eq!(cvec.get_back(300), Some(reference[699]));
+    assert_eq!(cvec.get_back(999), Some(reference[0]));
+    assert_eq!(cvec.get_back(1000), None);
+    assert_eq!(cvec.get_back(usize::MAX), None);
+    assert_eq!(CVec::new().get_back(0), None);
+}
+
+#[test]
+fn auto_encoding() {
+    let sorted = (0..5000).map(|i| 100_000 + i * 3).collect::<Vec<u32>>();
+    let offset = (0..5000)
+        .map(|i| 1_000_000_000 + (i * 7919) % 1000)
+        .collect::<Vec<u32>>();
+    let mut seed = 42u32;
+    let random = (0..5000)
+        .map(|_| {
+            seed = seed.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            seed
+        })
+        .collect::<Vec<u32>>();
+
+    for (values, encoding) in [
+        (sorted, Encoding::Delta),
+        (offset, Encoding::FrameOfReference),
+        (random, Encoding::Plain),
+    ] {
+        let mut cvec = CVec::auto(&values);
+        assert_eq!(cvec.encoding(), encoding);
+        assert_eq!(cvec.as_vec(), values);
+        cvec.debug_assert_valid();
+
+        // Blocks keep their encoding valid after modifications
+        let mut values = values;
+        cvec.set(10, values[20]);
+        values[10] = values[20];
+        cvec.insert(300, values[300]);
+        values.insert(300, values[300]);
+        cvec.remove(7);
+        values.remove(7);
+        cvec.push(values[values.len() - 1]);
+        values.push(values[values.len() - 1]);
+        cvec.pop();
+        values.pop();
+        assert_eq!(cvec.as_vec(), values);
+    }
+
+    let sorted = (0..5000).map(|i| 100_000 + i * 3).collect::<Vec<u32>>();
+    assert!(CVec::auto(&sorted).byte_len() < CVec::from(sorted).byte_len());
+    assert_eq!(CVec::auto(&[]).encoding(), Encoding::Plain);
+}
+
+#[test]
+fn iter_ref_for_loop() {
+    let cvec = (0..1000).collect::<CVec>();
+
+    let mut sum = 0;
+    for i in &cvec {
+        sum += i;
+    }
+    assert_eq!(sum, (0..1000).sum());
+    assert_eq!((&cvec).into_iter().len(), cvec.len());
+}
+
+#[test]
+fn cvec64() {
+    let boundary = u32::MAX as u64;
+    let values = (0..1000u64)
+        .map(|i| boundary - 500 + i)
+        .chain([0, u64::MAX, 1 << 32, boundary])
+        .collect::<Vec<_>>();
+
+    let mut values = values;
+    let mut cvec = CVec64::new();
+    for val in values.iter() {
+        cvec.push(*val);
+    }
+    assert_eq!(cvec.len(), values.len());
+    assert_eq!(cvec.as_vec(), values);
+    assert_eq!(cvec, CVec64::from(values.as_slice()));
+    assert_eq!(cvec.iter().rev().collect::<Vec<_>>().len(), values.len());
+    for (i, val) in values.iter().enumerate() {
+        assert_eq!(cvec.get(i), Some(*val));
+    }
+    assert_eq!(cvec.get(values.len()), None);
+
+    cvec.set(3, u64::MAX).unwrap();
+    assert_eq!(cvec.get(3), Some(u64::MAX));
+    values[3] = u64::MAX;
+    assert_eq!(cvec.set(values.len(), 0), None);
+
+    for val in values.iter().rev() {
+        assert_eq!(cvec.pop(), Some(*val));
+    }
+    assert_eq!(cvec.pop(), None);
+    assert!(cvec.is_empty());
+
+    // The high lane of values fitting into 32 bits only stores the block headers
+    let small = (0..10_000u64).collect::<CVec64>();
+    let small32 = (0..10_000u32).collect::<CVec>();
+    assert!(small.byte_len() - small32.byte_len() < 100);
+}
+
+#[test]
+fn new_sorted() {
+    let mut cvec = CVec::new_sorted();
+    for i in 0..100_000 {
+        cvec.push(i);
+    }
+    assert_eq!(cvec.encoding(), Encoding::Delta);
+    assert_eq!(cvec, (0..100_000).collect::<Vec<_>>());
+    cvec.debug_assert_valid();
+
+    // Consecutive values only need a single bit per delta
+    let plain = (0..100_000).collect::<CVec>();
+    assert!(cvec.byte_len() * 8 < plain.byte_len());
+
+    // Equal values are allowed
+    cvec.push(99_999);
+    assert_eq!(cvec.last(), Some(99_999));
+}
+
+#[test]
+#[should_panic]
+fn new_sorted_unsorted_push() {
+    let mut cvec = CVec::new_sorted();
+    cvec.push(10);
+    cvec.push(9);
+}
+
+#[test]
+fn new_sorted_appends() {
+    let mut cvec = CVec::new_sorted();
+    cvec.extend(0..300);
+    cvec.extend_from_slice(&[300, 300, 301]);
+    cvec.extend_repeat(302, 600);
+    cvec.append(&mut (303..400).collect::<CVec>());
+    cvec.resize(1200, 400);
+    let mut cvec = CVec::concat(&[cvec, (400..500).collect()]);
+    assert_eq!(cvec.len(), 1300);
+    assert!(cvec.is_sorted());
+
+    // Filling keeps the order, so values still have to be sorted
+    cvec.fill(7);
+    cvec.push(7);
+    assert!(cvec.is_sorted());
+}
+
+#[test]
+#[should_panic(expected = "into sorted CVec")]
+fn new_sorted_unsorted_extend() {
+    let mut cvec = CVec::new_sorted();
+    cvec.extend(0..300);
+    cvec.extend(vec![300, 299]);
+}
+
+#[test]
+#[should_panic(expected = "into sorted CVec")]
+fn new_sorted_unsorted_extend_from_slice() {
+    let mut cvec = CVec::new_sorted();
+    cvec.extend(0..300);
+    cvec.extend_from_slice(&[299, 300, 0]);
+}
+
+#[test]
+#[should_panic(expected = "into sorted CVec")]
+fn new_sorted_unsorted_extend_repeat() {
+    let mut cvec = CVec::new_sorted();
+    cvec.extend(0..300);
+    cvec.extend_repeat(5, 1000);
+}
+
+#[test]
+#[should_panic(expected = "into sorted CVec")]
+fn new_sorted_unsorted_resize() {
+    let mut cvec = CVec::new_sorted();
+    cvec.extend(0..256);
+    cvec.resize(1000, 0);
+}
+
+#[test]
+#[should_panic(expected = "into sorted CVec")]
+fn new_sorted_unsorted_append() {
+    let mut cvec = CVec::new_sorted();
+    cvec.extend(0..256);
+    cvec.append(&mut (0..256).rev().collect::<CVec>());
+}
+
+#[test]
+#[should_panic(expected = "into sorted CVec")]
+fn new_sorted_unsorted_concat() {
+    let mut cvec = CVec::new_sorted();
+    cvec.extend(100..356);
+    CVec::concat(&[cvec, (0..256).collect()]);
+}
+
+#[test]
+#[should_panic(expected = "into sorted CVec")]
+fn new_sorted_fill() {
+    let mut cvec = CVec::new_sorted();
+    cvec.extend(0..300);
+    cvec.fill(7);
+    cvec.push(6);
+}
+
+#[test]
+fn new_sorted_in_place_changes() {
+    let changes: Vec<fn(&mut CVec)> = vec![
+        |v| v.set(10, 0).unwrap(),
+        |v| v.set_many(&[(10, 0), (280, 1)]).unwrap(),
+        |v| v.insert(10, 0),
+        |v| v.swap(0, 299),
+        |v| {
+            v.swap_remove(0);
+        },
+        |v| v.reverse(),
+        |v| v.rotate_left(100),
+        |v| v.rotate_right(100),
+        |v| BufCVecMut::new(v).set_buffered(10, 0).unwrap(),
+    ];
+
+    for change in changes {
+        let mut cvec = CVec::new_sorted();
+        cvec.extend(1..=300);
+        change(&mut cvec);
+        assert!(!cvec.is_sorted());
+
+        // The vector isn't in the sorted mode anymore
+        cvec.push(0);
+        cvec.debug_assert_valid();
+    }
+}
+
+#[test]
+fn compression_ratio() {
+    let zeros = (0..100_000).map(|_| 0).collect::<CVec>();
+    assert!(zeros.compression_ratio() > 100.0);
+    assert!(zeros.block_bit_sizes().iter().all(|i| *i == 0));
+
+    let mut seed = 7u32;
+    let random = (0..100_000)
+        .map(|_| {
+            seed = seed.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            seed | 1 << 31
+        })
+        .collect::<CVec>();
+    let ratio = random.compression_ratio();
+    assert!(ratio > 0.9 && ratio <= 1.0, "{}", ratio);
+    assert!(random.block_bit_sizes().iter().all(|i| *i == 32));
+
+    let small = (0..1000).map(|i| i % 16).collect::<CVec>();
+    assert_eq!(small.block_bit_sizes(), vec![4; 4]);
+    assert_eq!(CVec::new().block_bit_sizes(), vec![]);
+
+    // Preallocated blocks don't hold values
+    let mut reserved = (0..300).map(|i| -(i % 2) as u32).collect::<CVec>();
+    reserved.reserve(1000);
+    assert_eq!(reserved.block_count(), 2);
+    assert_eq!(reserved.block_bit_sizes(), vec![1, 1]);
+    assert_eq!(reserved.block_signed_flags(), vec![true, true]);
+}
+
+#[test]
+fn hash_set() {
+    use std::collections::hash_map::DefaultHasher;
+    use std::collections::HashSet;
+    use std::hash::{Hash, Hasher};
+
+    let pushed = {
+        let mut cvec = CVec::new();
+        for i in 0..1000 {
+            cvec.push(i);
+        }
+        cvec
+    };
+    let popped = {
+        // Holds stale values behind its end
+        let mut cvec = (0..1200).collect::<CVec>();
+        for _ in 0..200 {
+            cvec.pop();
+        }
+        cvec
+    };
+    let with_capacity = {
+        let mut cvec = CVec::with_capacity(5000);
+        cvec.extend(0..1000);
+        cvec
+    };
+
+    let mut set = HashSet::new();
+    set.insert((0..1000).collect::<CVec>());
+    set.insert(pushed);
+    set.insert(popped);
+    set.insert(with_capacity);
+    set.insert(CVec::auto(&(0..1000).collect::<Vec<_>>()));
+    assert_eq!(set.len(), 1);
+
+    set.insert((0..999).collect::<CVec>());
+    set.insert(CVec::new());
+    assert_eq!(set.len(), 3);
+
+    // Equal values hash equally regardless of their compression
+    let hash = |cvec: &CVec| {
+        let mut hasher = DefaultHasher::new();
+        cvec.hash(&mut hasher);
+        hasher.finish()
+    };
+    let values = (0..300).collect::<Vec<u32>>();
+    let plain = CVec::from(values.as_slice());
+    let delta = CVec::auto(&values);
+    assert_ne!(plain.encoding(), delta.encoding());
+    assert_eq!(hash(&plain), hash(&delta));
+}
+
+#[test]
+fn ord() {
+    let vecs: Vec<Vec<u32>> = vec![
+        vec![],
+        vec![0],
+        vec![1],
+        vec![0, 0],
+        vec![0, 1],
+        (0..300).collect(),
+        (0..301).collect(),
+        (0..300).map(|i| if i == 299 { 1000 } else { i }).collect(),
+        (1..2).chain(0..600).collect(),
+        vec![u32::MAX; 257],
+    ];
+
+    for a in vecs.iter() {
+        for b in vecs.iter() {
+            let ca = CVec::from(a.as_slice());
+            let cb = CVec::from(b.as_slice());
+            assert_eq!(ca.cmp(&cb), a.cmp(b), "{:?} {:?}", a, b);
+            assert_eq!(ca.partial_cmp(&cb), a.partial_cmp(b));
+        }
+    }
+
+    let mut cvecs = vecs
+        .iter()
+        .map(|i| CVec::from(i.as_slice()))
+        .collect::<Vec<_>>();
+    cvecs.sort();
+    let mut vecs = vecs;
+    vecs.sort();
+    assert_eq!(
+        cvecs,
+        vecs.iter()
+            .map(|i| CVec::from(i.as_slice()))
+            .collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn first() {
+    assert_eq!(CVec::new().first(), None);
+    assert_eq!(CVec::with_capacity(1000).first(), None);
+
+    let mut cvec = (5..1000).collect::<CVec>();
+    assert_eq!(cvec.first(), Some(5));
+    cvec.remove(0);
+    assert_eq!(cvec.first(), Some(6));
+    cvec.clear();
+    assert_eq!(cvec.first(), None);
+}
+
+#[test]
+fn dedup_keeps_encoding() {
+    let mut cvec = CVec::new_sorted();
+    cvec.extend((0..1000).map(|i| i / 3));
+    cvec.dedup_by_key(|i| i);
+    assert_eq!(cvec, (0..334).collect::<Vec<_>>());
+    assert_eq!(cvec.encoding(), CVec::new_sorted().encoding());
+    assert_eq!(cvec.clone_range(1..10).encoding(), cvec.encoding());
+}
+
+#[test]
+fn push_staged_tail() {
+    let mut cvec = CVec::new();
+    let mut reference = Vec::new();
+
+    for i in 0..1000u32 {
+        cvec.push(i * 7);
+        reference.push(i * 7);
+    }
+    cvec.debug_assert_valid();
+    assert_eq!(cvec.get(999), Some(999 * 7));
+    assert_eq!(cvec.last(), reference.last().copied());
+    assert_eq!(cvec.iter().next_back(), reference.last().copied());
+    assert_eq!(cvec.to_bytes(), CVec::from(reference.as_slice()).to_bytes());
+    assert_eq!(
+        cvec.block_bit_sizes(),
+        CVec::from(reference.as_slice()).block_bit_sizes()
+    );
+    assert!(!cvec.is_all_zero());
+
+    // Modifications of the staged block
+    cvec.set(998, 1);
+    reference[998] = 1;
+    assert_eq!(cvec.pop(), reference.pop());
+    cvec.insert(900, 5);
+    reference.insert(900, 5);
+    cvec.push(3);
+    reference.push(3);
+    cvec.remove(950);
+    reference.remove(950);
+    cvec.extend_from_slice(&[1, 2, 3]);
+    reference.extend_from_slice(&[1, 2, 3]);
+    cvec.push(4);
+    reference.push(4);
+    cvec.truncate(1001);
+    reference.truncate(1001);
+    cvec.push(9);
+    reference.push(9);
+    cvec.set_many(&[(1001, 10), (0, 11)]);
+    reference[1001] = 10;
+    reference[0] = 11;
+    assert_eq!(cvec, reference);
+    cvec.debug_assert_valid();
+
+    // Popping across block boundaries
+    while let Some(val) = reference.pop() {
+        assert_eq!(cvec.pop(), Some(val));
+        if reference.len() % 300 == 0 {
+            assert_eq!(cvec, reference);
+        }
+    }
+    assert!(cvec.is_empty());
+    cvec.push(1);
+    assert_eq!(cvec, vec![1]);
+
+    let mut other = (0..10).collect::<CVec>();
+    cvec.append(&mut other);
+    assert_eq!(cvec, [1, 0, 1, 2, 3, 4, 5, 6, 7, 8, 9]);
+}
+
+#[test]
+fn collect_refs() {
+    let cvec = (0..10u32).collect::<Vec<_>>().iter().collect::<CVec>();
+    assert_eq!(cvec, (0..10).collect::<Vec<_>>());
+
+    let values = (0..1000).map(|i| i * 11).collect::<Vec<u32>>();
+    assert_eq!(values.iter().collect::<CVec>(), values);
+}
+
+#[test]
+fn contains() {
+    let mut cvec = (0..1000).map(|i| i * 2).collect::<CVec>();
+    assert!(cvec.contains(0));
+    assert!(cvec.contains(600));
+    assert!(!cvec.contains(601));
+    assert!(!cvec.contains(2000));
+    assert!(!cvec.contains(u32::MAX));
+
+    // Only in the last, partially filled block
+    cvec.push(u32::MAX);
+    assert!(cvec.contains(u32::MAX));
+    cvec.extend_from_slice(&[5001, 5003]);
+    assert!(cvec.contains(5003));
+
+    // Values behind the end of the vector don't count
+    cvec.pop();
+    assert!(!cvec.contains(5003));
+    assert!(!CVec::new().contains(0));
+    assert!(CVec::from(vec![-3i32 as u32; 300]).contains(-3i32 as u32));
+}
+
+#[test]
+fn binary_search() {
+    let values = (0..10_000).step_by(3).collect::<Vec<u32>>();
+
+    for cvec in [CVec::from(values.as_slice()), CVec::auto(&values)] {
+        for val in (0..10_010).chain([u32::MAX]) {
+            assert_eq!(
+                cvec.binary_search(val),
+                values.binary_search(&val),
+                "{}",
+                val
+            );
+        }
+    }
+
+    // Duplicates at block boundaries
+    let values = (0..1000).map(|i| i / 300).collect::<Vec<u32>>();
+    let cvec = CVec::from(values.as_slice());
+    for val in 0..5 {
+        match (cvec.binary_search(val), values.binary_search(&val)) {
+            (Ok(got), Ok(_)) => assert_eq!(values[got], val),
+            (got, exp) => assert_eq!(got, exp),
+        }
+    }
+
+    assert_eq!(CVec::new().binary_search(1), Err(0));
+}
+
+#[test]
+fn range_scan() {
+    let values = (0..5000u32).map(|i| i * 2 + (i % 7)).collect::<Vec<_>>();
+    let mut cvec = CVec::new();
+    for val in values.iter() {
+        cvec.push(*val);
+    }
+    cvec.debug_assert_valid();
+
+    for (lo, hi) in [(0, 0), (100, 200), (511, 1030), (9000, 20000), (3, 2)] {
+        let expected = values
+            .iter()
+            .copied()
+            .enumerate()
+            .filter(|(_, val)| (lo..=hi).contains(val))
+            .collect::<Vec<_>>();
+        assert_eq!(cvec.range_scan(lo, hi).collect::<Vec<_>>(), expected);
+
+        // Only blocks holding matching values get decompressed
+        let blocks = cvec.blocks_in_range(lo, hi).collect::<Vec<_>>();
+        let mut expected_blocks = expected.iter().map(|(i, _)| i / 256).collect::<Vec<_>>();
+        expected_blocks.dedup();
+        assert_eq!(blocks, expected_blocks);
+    }
+
+    // Bounds follow modifications
+    cvec.set(10, 1_000_000);
+    cvec.set(4999, 2_000_000);
+    assert_eq!(
+        cvec.range_scan(999_999, 3_000_000).collect::<Vec<_>>(),
+        vec![(10, 1_000_000), (4999, 2_000_000)]
+    );
+    assert_eq!(
+        cvec.blocks_in_range(999_999, 3_000_000).collect::<Vec<_>>(),
+        vec![0, 19]
+    );
+    cvec.truncate(4000);
+    cvec.extend_repeat(7, 1000);
+    assert_eq!(cvec.range_scan(7, 7).count(), 1000);
+    cvec.debug_assert_valid();
+    assert_eq!(CVec::new().range_scan(0, u32::MAX).count(), 0);
+}
+
+#[test]
+fn swap() {
+    let mut reference = (0..1000).map(|i| i * 3).collect::<Vec<u32>>();
+    let mut cvec = CVec::from(reference.as_slice());
+
+    for (a, b) in [(0, 1), (0, 999), (255, 256), (300, 310), (998, 990), (5, 5)] {
+        cvec.swap(a, b);
+        reference.swap(a, b);
+        assert_eq!(cvec, reference);
+    }
+
+    // Within the staged last block
+    cvec.push(7);
+    reference.push(7);
+    cvec.swap(1000, 999);
+    reference.swap(1000, 999);
+    assert_eq!(cvec, reference);
+    cvec.debug_assert_valid();
+}
+
+#[test]
+#[should_panic]
+fn swap_out_of_bounds() {
+    let mut cvec = (0..10).collect::<CVec>();
+    cvec.swap(3, 10);
+}
+
+#[test]
+fn reverse() {
+    for len in [0, 1, 255, 256, 257, 512, 1000, 1024] {
+        let mut reference = (0..len).map(|i| i * 5 + i % 3).collect::<Vec<u32>>();
+        let mut cvec = CVec::from(reference.as_slice());
+
+        cvec.reverse();
+        reference.reverse();
+        assert_eq!(cvec, reference);
+        assert_eq!(cvec.len(), reference.len());
+        cvec.debug_assert_valid();
+
+        cvec.reverse();
+        reference.reverse();
+        assert_eq!(cvec, reference);
+    }
+
+    // Preallocated blocks stay behind the used ones
+    let mut cvec = CVec::with_capacity(2000);
+    cvec.extend(0..512);
+    cvec.reverse();
+    assert_eq!(cvec, (0..512).rev().collect::<Vec<_>>());
+    assert_eq!(cvec.capacity(), 2048);
+    cvec.push(1);
+    assert_eq!(cvec.last(), Some(1));
+}
+
+#[test]
+fn sort_unstable() {
+    let mut seed = 3u32;
+    let reference = (0..3000)
+        .map(|_| {
+            seed = seed.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            seed >> 12
+        })
+        .collect::<Vec<u32>>();
+    let mut sorted = reference.clone();
+    sorted.sort_unstable();
+
+    let mut cvec = CVec::from(reference.as_slice());
+    cvec.sort_unstable();
+    assert_eq!(cvec, sorted);
+    assert_eq!(cvec.encoding(), Encoding::Plain);
+    let plain_len = cvec.byte_len();
+
+    let mut cvec = CVec::from(reference.as_slice());
+    cvec.sort_unstable_delta();
+    assert_eq!(cvec, sorted);
+    assert_eq!(cvec.encoding(), Encoding::Delta);
+    assert!(cvec.byte_len() < plain_len);
+    cvec.push(u32::MAX);
+    cvec.debug_assert_valid();
+}
+
+#[test]
+fn dedup() {
+    // Runs spanning block boundaries
+    let mut reference = (0..3000)
+        .map(|i| i / 100 + (i % 7 == 0) as u32)
+        .collect::<Vec<u32>>();
+    let mut cvec = CVec::from(reference.as_slice());
+    cvec.dedup();
+    reference.dedup();
+    assert_eq!(cvec, reference);
+
+    let mut cvec = CVec::from(vec![4u32; 1000]);
+    cvec.dedup();
+    assert_eq!(cvec, [4]);
+
+    let mut cvec = (0..1000).collect::<CVec>();
+    cvec.dedup();
+    assert_eq!(cvec, (0..1000).collect::<Vec<_>>());
+
+    let mut cvec = CVec::new();
+    cvec.dedup();
+    assert!(cvec.is_empty());
+}
+
+#[test]
+fn retain() {
+    let mut reference = (0..2000).map(|i| i * 7 % 1001).collect::<Vec<u32>>();
+    let mut cvec = CVec::from(reference.as_slice());
+
+    cvec.retain(|i| i % 2 == 0);
+    reference.retain(|i| i % 2 == 0);
+    assert_eq!(cvec, reference);
+    cvec.debug_assert_valid();
+
+    cvec.retain(|_| true);
+    assert_eq!(cvec, reference);
+    cvec.retain(|_| false);
+    assert!(cvec.is_empty());
+}
+
+#[test]
+fn split_off() {
+    let values = (0..1000).map(|i| i * 3).collect::<Vec<u32>>();
+
+    for at in [0, 1, 255, 256, 300, 512, 999, 1000] {
+        let mut cvec = CVec::from(values.as_slice());
+        let other = cvec.split_off(at);
+
+        assert_eq!(cvec.len(), at);
+        assert_eq!(other.len(), 1000 - at);
+        assert_eq!(cvec, &values[..at]);
+        assert_eq!(other, &values[at..]);
+        cvec.debug_assert_valid();
+        other.debug_assert_valid();
+
+        cvec.push(1);
+        assert_eq!(cvec.get(at), Some(1));
+    }
+
+    // Staged values and preallocated blocks
+    let mut cvec = CVec::with_capacity(2000);
+    for i in 0..600 {
+        cvec.push(i);
+    }
+    let mut other = cvec.split_off(512);
+    assert_eq!(cvec, (0..512).collect::<Vec<_>>());
+    assert_eq!(other, (512..600).collect::<Vec<_>>());
+    other.push(600);
+    assert_eq!(other.last(), Some(600));
+    cvec.debug_assert_valid();
+}
+
+#[test]
+#[should_panic]
+fn split_off_out_of_bounds() {
+    (0..10).collect::<CVec>().split_off(11);
+}
+
+#[test]
+fn drain() {
+    let values = (0..1000).map(|i| i * 3).collect::<Vec<u32>>();
+
+    for (start, end) in [
+        (0, 0),
+        (0, 1000),
+        (10, 20),
+        (200, 300),
+        (256, 512),
+        (255, 769),
+        (999, 1000),
+    ] {
+        let mut reference = values.clone();
+        let mut cvec = CVec::from(values.as_slice());
+
+        let drained = cvec.drain(start..end).collect::<Vec<_>>();
+        let expected = reference.drain(start..end).collect::<Vec<_>>();
+        assert_eq!(drained, expected);
+        assert_eq!(cvec, reference);
+        cvec.debug_assert_valid();
+    }
+
+    // Dropping the iterator early still removes the whole range
+    let mut cvec = CVec::from(values.as_slice());
+    let mut drain = cvec.drain(100..700);
+    assert_eq!(drain.len(), 600);
+    assert_eq!(drain.next(), Some(300));
+    assert_eq!(drain.next_back(), Some(699 * 3));
+    drop(drain);
+    let mut reference = values.clone();
+    reference.drain(100..700);
+    assert_eq!(cvec, reference);
+
+    cvec.drain(..);
+    assert!(cvec.is_empty());
+}
+
+#[test]
+fn with_capacity_zero_bit_blocks() {
+    let mut cvec = CVec::with_capacity(1000);
+    assert_eq!(cvec.capacity(), 1024);
+    assert_eq!(cvec.get(0), None);
+
+    for i in 0..1000 {
+        cvec.push(i % 3);
+        assert_eq!(cvec.get(i as usize), Some(i % 3));
+    }
+    assert_eq!(cvec, (0..1000).map(|i| i % 3).collect::<Vec<_>>());
+    assert_eq!(cvec.capacity(), 1024);
+
+    // Blocks packed with 0 bits
+    let mut zeros = CVec::with_capacity(1000);
+    zeros.extend((0..700).map(|_| 0));
+    assert_eq!(zeros.block_bit_sizes(), vec![0; 3]);
+    assert_eq!(zeros, vec![0; 700]);
+    zeros.insert(0, 1);
+    zeros.remove(0);
+    assert_eq!(zeros, vec![0; 700]);
+
+    let mut same = CVec::with_encoding(Encoding::FrameOfReference);
+    same.extend((0..600).map(|_| 1_000_000));
+    assert_eq!(same.block_bit_sizes()[..2], [0, 0]);
+    assert_eq!(same, vec![1_000_000; 600]);
+
+    let mut same = CVec::new_sorted();
+    same.extend_from_slice(&[7; 600]);
+    assert_eq!(same, vec![7; 600]);
+}
+
+#[test]
+fn from_bytes() {
+    let values = (0..1000).map(|i| i * i).collect::<Vec<u32>>();
+    for cvec in [
+        CVec::from(values.as_slice()),
+        CVec::auto(&values),
+        (0..600i32).map(|i| -i as u32).collect::<CVec>(),
+        CVec::new(),
+    ] {
+        let bytes = cvec.to_bytes();
+        let parsed = CVec::from_bytes(&bytes).unwrap();
+        assert_eq!(parsed, cvec);
+        assert_eq!(parsed.to_bytes(), bytes);
+        parsed.debug_assert_valid();
+    }
+
+    let bytes = CVec::from(values.as_slice()).to_bytes();
+    assert_eq!(
+        CVec::from_bytes(&bytes[..bytes.len() - 1]),
+        Err(FromBytesError::UnexpectedEnd)
+    );
+    assert_eq!(
+        CVec::from_bytes(&bytes[..4]),
+        Err(FromBytesError::UnexpectedEnd)
+    );
+
+    let mut trailing = bytes.clone();
+    trailing.push(0);
+    assert_eq!(
+        CVec::from_bytes(&trailing),
+        Err(FromBytesError::TrailingBytes(1))
+    );
+
+    let mut invalid = bytes;
+    invalid[8] = 33;
+    assert_eq!(
+        CVec::from_bytes(&invalid),
+        Err(FromBytesError::InvalidHeader(33))
+    );
+
+    // A frame of reference block whose reference overflows when added to its values
+    let mut cvec = CVec::with_encoding(Encoding::FrameOfReference);
+    cvec.extend(1_000_000..1_000_256);
+    let mut overflowing = cvec.to_bytes();
+    overflowing[9..13].copy_from_slice(&u32::MAX.to_le_bytes());
+    let parsed = CVec::from_bytes(&overflowing).unwrap();
+    assert_eq!(parsed.get(0), Some(u32::MAX));
+    assert_eq!(parsed.get(1), Some(0));
+}
+
+#[test]
+fn get_range() {
+    let reference = (0..1000).map(|i| i * 7).collect::<Vec<u32>>();
+    let mut cvec = CVec::from(reference.as_slice());
+    let all = cvec.as_vec();
+
+    // Spanning 3 blocks
+    assert_eq!(cvec.get_range(200..700), all[200..700]);
+    assert_eq!(cvec.get_range(255..=512), all[255..=512]);
+    assert_eq!(cvec.get_range(..), all);
+    assert_eq!(cvec.get_range(999..), all[999..]);
+    assert_eq!(cvec.get_range(256..512), all[256..512]);
+
+    // Clamped to the length
+    assert_eq!(cvec.get_range(900..5000), all[900..]);
+    assert_eq!(cvec.get_range(..=usize::MAX), all);
+    assert!(cvec.get_range(1000..).is_empty());
+    assert!(cvec.get_range(2000..3000).is_empty());
+    #[allow(clippy::reversed_empty_ranges)]
+    let reversed = 500..400;
+    assert!(cvec.get_range(reversed).is_empty());
+
+    cvec.push(1);
+    assert_eq!(cvec.get_range(998..), vec![998 * 7, 999 * 7, 1]);
+}
+
+#[test]
+fn blocks() {
+    for len in [0, 1, 256, 700] {
+        let mut cvec = (0..len).map(|i| i * 3).collect::<CVec>();
+        cvec.push(5);
+
+        let blocks = cvec.blocks().collect::<Vec<_>>();
+        assert_eq!(blocks.len(), (len as usize + 1).div_ceil(256));
+        assert!(blocks[..blocks.len() - 1].iter().all(|i| i.len() == 256));
+        assert_eq!(
+            blocks.into_iter().flatten().collect::<Vec<_>>(),
+            cvec.iter().collect::<Vec<_>>()
+        );
+    }
+
+    assert_eq!(CVec::new().blocks().count(), 0);
+}
+
+#[test]
+fn extend_refs() {
+    let some_vec = (0..500).map(|i| i * 9).collect::<Vec<u32>>();
+    let mut cvec = (0..100).collect::<CVec>();
+    cvec.extend(some_vec.iter());
+
+    let mut reference = (0..100).collect::<Vec<u32>>();
+    reference.extend(some_vec.iter());
+    assert_eq!(cvec, reference);
+}
+
+#[test]
+fn fill_resize() {
+    let mut cvec = (0..5000).map(|i| i * 1000).collect::<CVec>();
+    let byte_len = cvec.byte_len();
+    cvec.fill(7);
+    assert_eq!(cvec, vec![7; 5000]);
+    assert!(cvec.byte_len() < byte_len / 7);
+    assert!(cvec.byte_len() < 20 * (3 * 32 + 1) + 64);
+    cvec.debug_assert_valid();
+
+    // Staged values get overwritten as well
+    cvec.push(1);
+    cvec.fill(0);
+    assert!(cvec.is_all_zero());
+    cvec.push(2);
+    assert_eq!(cvec.len(), 5002);
+    assert_eq!(cvec.last(), Some(2));
+
+    let mut reference = cvec.as_vec();
+    for (len, val) in [(6000, 3), (5500, 4), (100, 5), (0, 6), (300, 7), (300, 8)] {
+        cvec.resize(len, val);
+        reference.resize(len, val);
+        assert_eq!(cvec, reference);
+    }
+    cvec.debug_assert_valid();
+}
+
+#[test]
+#[cfg(feature = "rayon")]
+fn from_par_slice() {
+    for len in [0, 1, 256, 1000, 100_000] {
+        let values = (0..len).map(|i| i * 31 % 1_000_003).collect::<Vec<u32>>();
+        let cvec = CVec::from_par_slice(&values);
+        assert_eq!(cvec, CVec::from(values.as_slice()));
+        assert_eq!(cvec.to_bytes(), CVec::from(values.as_slice()).to_bytes());
+        cvec.debug_assert_valid();
+    }
+
+    let mut cvec = CVec::from_par_slice(&[1, 2, 3]);
+    cvec.push(4);
+    assert_eq!(cvec, [1, 2, 3, 4]);
+}
+
+#[test]
+#[cfg(feature = "rayon")]
+fn par_iter() {
+    use rayon::prelude::*;
+
+    let values = (0..100_000).map(|i| i * 7 % 10_007).collect::<Vec<u32>>();
+    let mut cvec = CVec::from(values.as_slice());
+    cvec.push(5);
+
+    let sum: u64 = cvec.par_iter().map(|i| i as u64).sum();
+    assert_eq!(sum, cvec.iter().map(|i| i as u64).sum::<u64>());
+    assert_eq!(cvec.par_iter().collect::<Vec<_>>(), cvec.as_vec());
+    assert_eq!(CVec::new().par_iter().count(), 0);
+}
+
+#[test]
+fn display() {
+    assert_eq!(CVec::new().to_string(), "[]");
+    assert_eq!(CVec::from(&[1, 2, 3]).to_string(), "[1, 2, 3]");
+
+    let cvec = (0..1000).collect::<CVec>();
+    let expected = format!(
+        "[{}, ...]",
+        (0..32)
+            .map(|i| i.to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+    assert_eq!(cvec.to_string(), expected);
+    assert_eq!(
+        (0..32).collect::<CVec>().to_string().matches("...").count(),
+        0
+    );
+}
+
+#[test]
+fn iter_from() {
+    let values = (0..10_000).map(|i| i * 3 % 1117).collect::<Vec<u32>>();
+    let cvec = CVec::from(values.as_slice());
+
+    for start in [0, 1, 255, 256, 5000, 9999, 10_000, 20_000].iter().copied() {
+        let iter = cvec.iter_from(start);
+        assert_eq!(iter.len(), values.len().saturating_sub(start));
+        assert!(iter.eq(cvec.iter().skip(start)));
+    }
+
+    assert!(cvec
+        .iter_from(5000)
+        .rev()
+        .eq(values[5000..].iter().rev().copied()));
+}
+
+#[test]
+fn count_matching() {
+    let cvec = (0..10_000).map(|i| i * 7919 % 10_007).collect::<CVec>();
+
+    for threshold in [0, 1, 5000, 10_006, u32::MAX].iter().copied() {
+        assert_eq!(
+            cvec.count_matching(|i| i > threshold),
+            cvec.iter().filter(|i| *i > threshold).count()
+        );
+    }
+
+    // Padding of the last block must not be counted
+    let cvec = CVec::from(&[0, 0, 1]);
+    assert_eq!(cvec.count_matching(|i| i == 0), 2);
+    assert_eq!(CVec::new().count_matching(|_| true), 0);
+}
+
+#[test]
+fn aggregates() {
+    let empty = CVec::new();
+    assert_eq!(empty.sum(), 0);
+    assert_eq!(empty.min_value(), None);
+    assert_eq!(empty.max_value(), None);
+
+    let mut seed = 0x2545_f491_u32;
+    let mut cvec = (0..5000)
+        .map(|_| {
+            seed ^= seed << 13;
+            seed ^= seed >> 17;
+            seed ^= seed << 5;
+            seed
+        })
+        .collect::<CVec>();
+
+    for _ in 0..2 {
+        assert_eq!(cvec.sum(), cvec.iter().map(|i| i as u64).sum::<u64>());
+        assert_eq!(cvec.min_value(), cvec.iter().min());
+        assert_eq!(cvec.max_value(), cvec.iter().max());
+
+        // Loose bounds after popping and setting must not change the results
+        let max_pos = cvec
+            .iter()
+            .position(|i| Some(i) == cvec.max_value())
+            .unwrap();
+        cvec.set(max_pos, 1);
+        cvec.pop();
+    }
+}
+
+#[test]
+fn zero_bit_blocks() {
+    let encodings = [Encoding::Plain, Encoding::Delta, Encoding::FrameOfReference];
+    for encoding in encodings.iter().copied() {
+        let mut cvec = CVec::with_encoding(encoding);
+        cvec.push(0);
+        assert_eq!(cvec.get(0), Some(0));
+        assert_eq!(cvec.as_vec(), vec![0]);
+
+        // A full block of zeros compresses to no packed data at all
+        cvec.extend((0..300).map(|_| 0));
+        cvec.shrink_to_fit();
+        assert_eq!(cvec.get(255), Some(0));
+        assert_eq!(cvec.iter().filter(|i| *i == 0).count(), 301);
+
+        let parsed = CVec::from_bytes(&cvec.to_bytes()).unwrap();
+        assert_eq!(parsed, cvec);
+    }
+}
+
+#[test]
+fn as_slice_cached() {
+    let values = (0..1000).map(|i| i * 5 % 503).collect::<Vec<u32>>();
+    let mut cvec = CVec::from(values.as_slice());
+    assert_eq!(cvec.as_slice_cached(), values.as_slice());
+    assert_eq!(cvec.as_slice_cached()[999], values[999]);
+
+    cvec.set(10, 1_000_000);
+    assert_eq!(cvec.as_slice_cached()[10], 1_000_000);
+    cvec.debug_assert_valid();
+
+    cvec.push(7);
+    assert_eq!(cvec.as_slice_cached().len(), 1001);
+    cvec.pop();
+    cvec.pop();
+    assert_eq!(cvec.as_slice_cached().len(), 999);
+    cvec.debug_assert_valid();
+
+    let mut other = cvec.clone();
+    other.as_slice_cached();
+    cvec.append(&mut other);
+    assert!(other.as_slice_cached().is_empty());
+    assert_eq!(cvec.as_slice_cached().len(), 1998);
+
+    cvec.truncate(3);
+    assert_eq!(cvec.as_slice_cached(), &values[..3]);
+    cvec.clear();
+    assert!(cvec.as_slice_cached().is_empty());
+    cvec.debug_assert_valid();
+}
+
+#[test]
+fn try_as_vec() {
+    let values = (0..1000).map(|i| i * 60 % 65_536).collect::<Vec<u32>>();
+    let mut cvec = CVec::from(values.as_slice());
+    let narrow = cvec.try_as_vec::<u16>().unwrap();
+    assert!(narrow.iter().map(|i| *i as u32).eq(values.iter().copied()));
+
+    let small = CVec::from(&[0, 1, 255]).try_as_vec::<u8>().unwrap();
+    assert_eq!(small, vec![0, 1, 255]);
+
+    cvec.set(700, 70_000);
+    assert_eq!(
+        cvec.try_as_vec::<u16>(),
+        Err(ValueOverflowError {
+            pos: 700,
+            value: 70_000
+        })
+    );
+    assert_eq!(
+        CVec::from(&[1, 2, 256, 300]).try_as_vec::<u8>(),
+        Err(ValueOverflowError { pos: 2, value: 256 })
+    );
+}
+
+#[test]
+fn chunks() {
+    let cvec = (0..1000).map(|i| i * 13 % 997).collect::<CVec>();
+
+    let chunks = cvec.chunks(300).collect::<Vec<_>>();
+    assert_eq!(
+        chunks.iter().map(|i| i.len()).collect::<Vec<_>>(),
+        vec![300, 300, 300, 100]
+    );
+    assert_eq!(chunks.concat(), cvec.as_vec());
+
+    assert_eq!(cvec.chunks(256).count(), 4);
+    assert_eq!(cvec.chunks(5000).next().unwrap(), cvec.as_vec());
+    assert_eq!(CVec::new().chunks(3).count(), 0);
+}
+
+#[test]
+#[should_panic]
+fn chunks_zero() {
+    let _ = CVec::new().chunks(0);
+}
+
+#[test]
+fn windows() {
+    let cvec = (0..600).map(|i| i * 31 % 599).collect::<CVec>();
+    let values = cvec.as_vec();
+
+    for size in [1, 2, 3, 256, 257, 600, 601].iter().copied() {
+        let windows = cvec.windows(size).collect::<Vec<_>>();
+        let expected = values.windows(size).map(|i| i.to_vec()).collect::<Vec<_>>();
+        assert_eq!(windows, expected);
+    }
+
+    assert_eq!(CVec::new().windows(1).count(), 0);
+}
+
+#[test]
+fn eq_array() {
+    assert_eq!([0, 1, 2], (0..3).collect::<CVec>());
+    assert_eq!((0..3).collect::<CVec>(), [0, 1, 2]);
+    assert_ne!([0, 1], (0..3).collect::<CVec>());
+    assert_ne!([0, 1, 3], (0..3).collect::<CVec>());
+    assert_eq!([0u32; 0], CVec::new());
+
+    let big = [7; 300];
+    assert_eq!(big, CVec::from(&big));
+}
+
+#[test]
+fn swap_remove() {
+    let mut vec = (0..1000).map(|i| i * 17 % 991).collect::<Vec<u32>>();
+    let mut cvec = CVec::from(vec.as_slice());
+
+    for pos in [0, 500, 255, 256, 990, 993].iter().copied() {
+        assert_eq!(cvec.swap_remove(pos), vec.swap_remove(pos));
+        assert_eq!(cvec, vec);
+        cvec.debug_assert_valid();
+    }
+
+    // Staged last block
+    cvec.push(12345);
+    vec.push(12345);
+    assert_eq!(
+        cvec.swap_remove(cvec.len() - 3),
+        vec.swap_remove(vec.len() - 3)
+    );
+    assert_eq!(
+        cvec.swap_remove(cvec.len() - 1),
+        vec.swap_remove(vec.len() - 1)
+    );
+    assert_eq!(cvec, vec);
+    cvec.debug_assert_valid();
+
+    let mut single = CVec::from(&[4]);
+    assert_eq!(single.swap_remove(0), 4);
+    assert!(single.is_empty());
+}
+
+#[test]
+#[should_panic]
+fn swap_remove_out_of_bounds() {
+    CVec::from(&[1, 2]).swap_remove(2);
+}
+
+#[test]
+fn rotate() {
+    for len in [0, 10, 512, 700].iter().copied() {
+        let values = (0..len).map(|i| i * 7 % 509).collect::<Vec<u32>>();
+
+        for n in [0, 1, 3, 256, 300, 512, 700].iter().copied() {
+            if n > values.len() {
+                continue;
+            }
+
+            let mut vec = values.clone();
+            let mut cvec = CVec::from(values.as_slice());
+            vec.rotate_left(n);
+            cvec.rotate_left(n);
+            assert_eq!(cvec, vec);
+            cvec.debug_assert_valid();
+
+            let mut vec = values.clone();
+            let mut cvec = CVec::from(values.as_slice());
+            vec.rotate_right(n);
+            cvec.rotate_right(n);
+            assert_eq!(cvec, vec);
+            cvec.debug_assert_valid();
+        }
+    }
+}
+
+#[test]
+#[should_panic]
+fn rotate_out_of_bounds() {
+    CVec::from(&[1, 2]).rotate_right(3);
+}
+
+#[test]
+fn block_at() {
+    let mut cvec = CVec::with_capacity(2000);
+    assert_eq!(cvec.block_count(), 0);
+    assert!(cvec.block_at(0).is_none());
+
+    cvec.extend(0..600);
+    cvec.push(1);
+    assert_eq!(cvec.block_count(), cvec.len().div_ceil(256));
+    assert!(cvec.block_at(cvec.block_count()).is_none());
+
+    let bit_sizes = cvec.block_bit_sizes();
+    for (block_nr, bits) in bit_sizes.iter().enumerate().take(cvec.block_count()) {
+        let (header, data) = cvec.block_at(block_nr).unwrap();
+        assert_eq!(header & 0b0011_1111, *bits);
+        assert_eq!(data.len(), 32 * *bits as usize);
+    }
+
+    let values = [3; 256];
+    let cvec = CVec::from(&values);
+    let (header, data) = cvec.block_at(0).unwrap();
+    assert_eq!(
+        (header, data.into_owned()),
+        CVec::from_raw_block(2, &values)
+    );
+}
+
+#[test]
+fn from_blocks() {
+    let mut cvec = (0..1000).map(|i| i * 3 % 211).collect::<CVec>();
+    cvec.push(70_000);
+
+    let blocks = (0..cvec.block_count())
+        .map(|block_nr| {
+            let (header, data) = cvec.block_at(block_nr).unwrap();
+            (header, data.into_owned())
+        })
+        .collect::<Vec<_>>();
+
+    let mut rebuilt = CVec::from_blocks(blocks.clone(), cvec.len()).unwrap();
+    assert_eq!(rebuilt, cvec);
+    rebuilt.debug_assert_valid();
+    rebuilt.push(5);
+    assert_eq!(rebuilt.last(), Some(5));
+
+    assert_eq!(
+        CVec::from_blocks(blocks.clone(), 500).unwrap_err(),
+        FromBlocksError::BlockCountMismatch(4)
+    );
+    assert_eq!(
+        CVec::from_blocks(blocks.clone(), 1025).unwrap_err(),
+        FromBlocksError::BlockCountMismatch(4)
+    );
+
+    let mut invalid = blocks.clone();
+    invalid[2].1.pop();
+    assert_eq!(
+        CVec::from_blocks(invalid, cvec.len()).unwrap_err(),
+        FromBlocksError::InvalidBlockLen(2)
+    );
+
+    let mut invalid = blocks;
+    invalid[0].0 = 40;
+    assert_eq!(
+        CVec::from_blocks(invalid, cvec.len()).unwrap_err(),
+        FromBlocksError::InvalidHeader(40)
+    );
+
+    assert_eq!(CVec::from_blocks(Vec::new(), 0).unwrap(), CVec::new());
+}
+
+#[test]
+fn is_sorted() {
+    assert!(CVec::new().is_sorted());
+    assert!(CVec::from(&[5]).is_sorted());
+    assert!((0..1000).collect::<CVec>().is_sorted());
+    assert!(!(0..1000).rev().collect::<CVec>().is_sorted());
+
+    // Flat, then descending across a block boundary
+    let mut cvec = (0..256).map(|_| 7).collect::<CVec>();
+    assert!(cvec.is_sorted());
+    cvec.push(6);
+    assert!(!cvec.is_sorted());
+
+    // Descending within the last, partially filled block
+    let mut cvec = (0..300).collect::<CVec>();
+    cvec.push(299);
+    assert!(cvec.is_sorted());
+    cvec.push(0);
+    assert!(!cvec.is_sorted());
+}
+
+#[test]
+fn get_unchecked() {
+    let mut cvec = (0..1000).map(|i| i * 7).collect::<CVec>();
+    cvec.push(1);
+
+    for pos in 0..cvec.len() {
+        assert_eq!(Some(unsafe { cvec.get_unchecked(pos) }), cvec.get(pos));
+    }
+    assert_eq!(unsafe { cvec.get_unchecked(1000) }, 1);
+}
+
+#[test]
+fn get_into() {
+    let mut cvec = (0..1000).map(|i| i * 7).collect::<CVec>();
+    cvec.push(1);
+
+    let mut scratch = Vec::new();
+    for pos in (0..cvec.len()).rev() {
+        assert_eq!(cvec.get_into(pos, &mut scratch), cvec.get(pos));
+    }
+    assert_eq!(cvec.get_into(1001, &mut scratch), None);
+    assert!(scratch.len() >= 256);
+}
+
+#[test]
+fn optimize() {
+    // A block packed with far more bits than its values need
+    let values = [1; 256];
+    let inflated = CVec::from_raw_block(32, &values);
+    let mut cvec = CVec::from_blocks(vec![inflated], 256).unwrap();
+    let before = cvec.byte_len();
+    let capacity = cvec.capacity_in_bytes();
+
+    assert_eq!(cvec.optimize(), before - cvec.byte_len());
+    assert!(cvec.capacity_in_bytes() <= capacity - (before - cvec.byte_len()));
+    assert_eq!(cvec.block_bit_sizes(), vec![1]);
+    assert_eq!(cvec, values);
+    assert_eq!(cvec.optimize(), 0);
+
+    // The last block keeps the bitsize of popped values
+    let mut cvec = (0..300).map(|i| i % 4).collect::<CVec>();
+    cvec.push(u32::MAX);
+    cvec.shrink_to_fit();
+    cvec.pop();
+    let before = cvec.byte_len();
+    assert!(cvec.optimize() > 0);
+    assert!(cvec.byte_len() < before);
+    assert_eq!(cvec, (0..300).map(|i| i % 4).collect::<Vec<_>>());
+    cvec.debug_assert_valid();
+}
+
+#[test]
+fn position() {
+    let cvec = (0..5000).map(|i| i * 7919 % 5003).collect::<CVec>();
+
+    for threshold in [0, 4000, 5000, 5002, u32::MAX].iter().copied() {
+        assert_eq!(
+            cvec.position(|i| i > threshold),
+            cvec.iter().position(|i| i > threshold)
+        );
+    }
+
+    // Padding of the last block must not match
+    let cvec = CVec::from(&[1, 1, 0]);
+    assert_eq!(cvec.position(|i| i == 0), Some(2));
+    assert_eq!(CVec::from(&[1, 1]).position(|i| i == 0), None);
+}
+
+#[test]
+fn rposition() {
+    let cvec = (0..5000).map(|i| i * 7919 % 5003).collect::<CVec>();
+
+    for threshold in [0, 4000, 5000, 5002, u32::MAX].iter().copied() {
+        assert_eq!(
+            cvec.rposition(|i| i > threshold),
+            cvec.iter().rposition(|i| i > threshold)
+        );
+    }
+
+    // The only match is in the first block, the padding of the last block must not match
+    let mut cvec = (0..600).map(|_| 1).collect::<CVec>();
+    cvec.set(3, 0);
+    assert_eq!(cvec.rposition(|i| i == 0), Some(3));
+    cvec.push(0);
+    assert_eq!(cvec.rposition(|i| i == 0), Some(600));
+    assert_eq!(CVec::new().rposition(|_| true), None);
+}
+
+#[test]
+fn heap_bytes() {
+    let mut cvec = (0..1000).collect::<CVec>();
+    cvec.reserve(5000);
+    assert!(cvec.heap_bytes() >= cvec.byte_len());
+
+    // The bounds of each block are counted on top of the block buffers
+    let capacity = cvec.capacity_in_bytes();
+    assert!(cvec.heap_bytes() > capacity);
+
+    let before = cvec.heap_bytes();
+    cvec.as_slice_cached();
+    assert!(cvec.heap_bytes() >= before + 1000 * 4);
+    assert_eq!(cvec.capacity_in_bytes(), capacity);
+}
+
+#[test]
+fn reserve() {
+    let mut cvec = (0..300).collect::<CVec>();
+    cvec.push(5);
+    let capacity = cvec.capacity();
+
+    let bytes = cvec.capacity_in_bytes();
+    cvec.reserve(1000);
+    assert!(cvec.capacity() >= 1301);
+    // Like `with_capacity`, each new block gets a buffer for 256 bytes
+    assert!(cvec.capacity_in_bytes() >= bytes + 4 * 256);
+    assert!(cvec.capacity() > capacity);
+    assert_eq!(cvec.len(), 301);
+    assert_eq!(cvec.last(), Some(5));
+    cvec.debug_assert_valid();
+
+    // Enough capacity already
+    let capacity = cvec.capacity();
+    cvec.reserve(10);
+    assert_eq!(cvec.capacity(), capacity);
+
+    cvec.extend(0..1000);
+    assert_eq!(cvec.capacity(), capacity);
+    assert_eq!(cvec.len(), 1301);
+    assert!(cvec.iter().skip(301).eq(0..1000));
+}
+
+#[test]
+#[should_panic(expected = "capacity overflow")]
+fn reserve_overflow() {
+    let mut cvec = (0..300).collect::<CVec>();
+    cvec.reserve(usize::MAX);
+}
+
+#[test]
+fn enumerate_blocks() {
+    let cvec = (0..1000).map(|i| i * 3).collect::<CVec>();
+
+    let mut count = 0;
+    for (pos, (block_nr, pos_in_block, val)) in cvec.iter().enumerate_blocks().enumerate() {
+        assert_eq!(block_nr, pos / 256);
+        assert_eq!(pos_in_block, pos % 256);
+        assert_eq!(val, pos as u32 * 3);
+        count += 1;
+    }
+    assert_eq!(count, 1000);
+
+    let mut iter = cvec.iter_from(510).enumerate_blocks();
+    assert_eq!(iter.len(), 490);
+    assert_eq!(iter.next(), Some((1, 254, 1530)));
+    assert_eq!(iter.nth(1), Some((2, 0, 1536)));
+}
+
+#[test]
+fn concat() {
+    let lens = [256, 100, 0, 512, 300, 1, 256, 700];
+    let mut vecs = Vec::new();
+    let mut expected = Vec::new();
+
+    for (i, len) in lens.iter().enumerate() {
+        let mut values = (0..*len as u32)
+            .map(|j| j * (i as u32 + 1))
+            .collect::<Vec<_>>();
+        let mut cvec = CVec::from(values.as_slice());
+        if i == 4 {
+            // Staged last block
+            cvec.push(9);
+            values.push(9);
+        }
+        expected.extend_from_slice(&values);
+        vecs.push(cvec);
+    }
+
+    let concatenated = CVec::concat(&vecs);
+    assert_eq!(concatenated, expected);
+    assert_eq!(concatenated.len(), expected.len());
+    concatenated.debug_assert_valid();
+
+    assert_eq!(CVec::concat(&[]), CVec::new());
+    let delta = CVec::concat(&[CVec::with_encoding(Encoding::Delta)]);
+    assert_eq!(delta.encoding(), Encoding::Delta);
+}
+
+#[test]
+fn builder() {
+    let values = (0..1000).map(|i| i * 7 % 331).collect::<Vec<u32>>();
+
+    let mut builder = CVecBuilder::with_capacity(values.len());
+    assert!(builder.is_empty());
+    for i in values[..500].iter() {
+        builder.push(*i);
+    }
+    builder.extend_from_slice(&values[500..900]);
+    builder.extend(values[900..].iter().copied());
+    assert_eq!(builder.len(), 1000);
+
+    let cvec = builder.build();
+    assert_eq!(cvec, values);
+    cvec.debug_assert_valid();
+
+    let mut builder = CVecBuilder::new().encoding(Encoding::Delta);
+    builder.extend(0..300);
+    let cvec = builder.build();
+    assert_eq!(cvec.encoding(), Encoding::Delta);
+    assert_eq!(cvec, (0..300).collect::<Vec<_>>());
+    assert_eq!(CVecBuilder::new().build(), CVec::new());
+}
+
+#[test]
+fn dedup_by_key_decades() {
+    // Runs within the same decade span block boundaries
+    let values = (0..3000).map(|i| i / 3).collect::<Vec<u32>>();
+
+    let mut cvec = CVec::from(values.as_slice());
+    let mut vec = values;
+    cvec.dedup_by_key(|i| i / 10);
+    vec.dedup_by_key(|i| *i / 10);
+
+    assert_eq!(cvec, vec);
+    assert_eq!(cvec.len(), 100);
+    cvec.debug_assert_valid();
+}
+
+#[test]
+fn shrink_to_fit_staged() {
+    let mut cvec = CVec::new();
+    cvec.push(1000);
+    assert_eq!(cvec.shrink_to_fit(), 0);
+    assert_eq!(cvec, [1000]);
+
+    let mut cvec = CVec::with_capacity(2000);
+    cvec.extend(0..300);
+    cvec.push(7);
+    let reclaimed = cvec.shrink_to_fit();
+    assert!(reclaimed > 0);
+    assert_eq!(cvec.shrink_to_fit(), 0);
+    assert_eq!(cvec.len(), 301);
+    assert_eq!(cvec.last(), Some(7));
+}